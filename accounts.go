@@ -0,0 +1,295 @@
+package main
+
+import (
+	"context"
+	"flag"
+	"fmt"
+	"log"
+	"sort"
+	"strings"
+	"sync"
+	"sync/atomic"
+	"time"
+
+	"github.com/cenkalti/backoff/v5"
+	"golang.org/x/oauth2"
+	"google.golang.org/api/gmail/v1"
+	"google.golang.org/api/option"
+)
+
+var accountsFlag = flag.String("accounts", "", "comma-separated Gmail addresses to count together; each is authorized once and cached in its own token file, and the report is the combined series followed by a per-account breakdown")
+
+// defaultAccountWorkers caps concurrent fetches across all of -accounts when
+// -workers is not set, so several mailboxes fetching at once stay well under
+// the API quotas.
+const defaultAccountWorkers = 20
+
+// sharedFetchLimiter, when set, is the one fetch semaphore handed to every
+// fetch loop, so concurrent accounts share a single -workers budget.
+var sharedFetchLimiter chan struct{}
+
+// accountList returns the addresses named by -accounts.
+func accountList() []string {
+	var accounts []string
+	for account := range strings.SplitSeq(*accountsFlag, ",") {
+		if account = strings.TrimSpace(account); account != "" {
+			accounts = append(accounts, account)
+		}
+	}
+	return accounts
+}
+
+// validateAccounts rejects flags -accounts cannot honour: the report modes and
+// anything tied to a single mailbox.
+func validateAccounts() error {
+	if len(accountList()) == 0 {
+		return nil
+	}
+	if *splitLabels || *senderDiversity || *bySender || *byDomain || *domainTiers || *byReason || *byListID || *byDaypart || *timestamps {
+		return fmt.Errorf("-accounts only supports the daily outputs")
+	}
+	if *deleteSpam || *checkpointPath != "" || *useIMAP || *sheetID != "" || *mergeFrom != "" || *listLabels {
+		return fmt.Errorf("-accounts cannot be combined with -delete, -checkpoint, -imap, -sheet-id, -merge or -list-labels")
+	}
+	if *mailbox != "me" {
+		return fmt.Errorf("-accounts cannot be combined with -mailbox")
+	}
+	for _, label := range labels {
+		if !systemLabels[label] && label != allMailLabel {
+			// User label IDs differ from one account to the next
+			return fmt.Errorf("-accounts only supports system labels such as SPAM, not %q", label)
+		}
+	}
+	return nil
+}
+
+// accountTokenPath returns the token cache for account, e.g.
+// token-alice@example.com.json beside the usual token.json.
+func accountTokenPath(config *oauth2.Config, account string) string {
+	safe := strings.Map(func(r rune) rune {
+		if r >= 'a' && r <= 'z' || r >= 'A' && r <= 'Z' || r >= '0' && r <= '9' || strings.ContainsRune("@.-_+", r) {
+			return r
+		}
+		return '_'
+	}, account)
+	return strings.TrimSuffix(tokenPath(config), ".json") + "-" + safe + ".json"
+}
+
+// accountRun is one account's share of an -accounts run. The accounts are
+// fetched concurrently, so their stats, left-out messages and progress are
+// kept apart to be reported per account rather than merged.
+type accountRun struct {
+	name     string
+	stats    runStats
+	undated  int          // collected messages without a usable date
+	dropped  atomic.Int64 // fetched messages dropped on cancellation
+	progress atomic.Int64 // messages listed so far, for the progress line
+}
+
+type accountKey struct{}
+
+// withAccount returns a context for fetching run's account.
+func withAccount(ctx context.Context, run *accountRun) context.Context {
+	return context.WithValue(ctx, accountKey{}, run)
+}
+
+// accountFrom returns the account ctx is fetching, or nil outside -accounts.
+func accountFrom(ctx context.Context) *accountRun {
+	run, _ := ctx.Value(accountKey{}).(*accountRun)
+	return run
+}
+
+// runAccountsReport authorizes each of -accounts in turn, since consent needs
+// the terminal, then fetches them all concurrently and prints the combined
+// report.
+func runAccountsReport(ctx context.Context, config *oauth2.Config) {
+	accounts := accountList()
+	services := make([]*gmail.Service, len(accounts))
+	for i, account := range accounts {
+		fmt.Fprintf(status, "Authorizing %s\n", account)
+		client := getClient(ctx, config, accountTokenPath(config, account), oauth2.SetAuthURLParam("login_hint", account))
+		srv, err := gmail.NewService(ctx, option.WithHTTPClient(client))
+		if err != nil {
+			fatalf("Unable to retrieve Gmail client for %s: %v", account, err)
+		}
+		services[i] = srv
+	}
+	if authCheck {
+		fmt.Printf("Cached tokens are valid for %d accounts\n", len(accounts))
+		return
+	}
+
+	if *warmup {
+		for _, srv := range services {
+			warmUpConnection(ctx, srv)
+		}
+	}
+
+	if *confirmAbove > 0 && !*yes {
+		estimate, err := estimateAccounts(ctx, services)
+		if err != nil {
+			fatalf("Error estimating spam counts: %v", err)
+		}
+		if err := confirmLargeRun(estimate); err != nil {
+			fatalf("Error getting spam counts: %v", err)
+		}
+	}
+	// The combined estimate has been confirmed, so the concurrent fetches
+	// must not prompt again on their own
+	largeRunConfirmed = true
+
+	if fetchAIMD == nil {
+		n := *workers
+		if n <= 0 {
+			n = defaultAccountWorkers
+		}
+		sharedFetchLimiter = make(chan struct{}, n)
+	}
+
+	runs := make([]*accountRun, len(accounts))
+	for i, account := range accounts {
+		runs[i] = &accountRun{name: account}
+	}
+	accountProgress = runs
+
+	stopHeartbeat := startHeartbeat()
+	defer stopHeartbeat()
+	accountCounts, err := getAccountSpamCounts(ctx, runs, services)
+	finishProgressLine()
+	if err != nil {
+		fatalf("Error getting spam counts: %v", err)
+	}
+
+	combined := make(map[string]int)
+	for _, counts := range accountCounts {
+		for date, count := range counts {
+			combined[date] += count
+		}
+	}
+	printReport(ctx, combined)
+	for i, run := range runs {
+		reconcile(run.name+": ", &run.stats, countsTotal(accountCounts[i]), int64(run.undated), run.dropped.Load())
+	}
+	if *showStats {
+		for _, run := range runs {
+			printRunStats(run.name+": ", &run.stats)
+		}
+	}
+	if *format == "text" && !*compact {
+		printAccountSummary(accounts, accountCounts)
+	}
+}
+
+// estimateAccounts sums the resultSizeEstimate of every label in every
+// account, so a large -accounts run is confirmed once before any fetching.
+func estimateAccounts(ctx context.Context, services []*gmail.Service) (int64, error) {
+	query := buildQuery()
+	var total int64
+	for _, srv := range services {
+		for _, label := range labels {
+			req := srv.Users.Messages.List(*mailbox).Q(query).MaxResults(1)
+			if label != allMailLabel {
+				req = req.LabelIds(label)
+			}
+			if includeSpamTrash {
+				req = req.IncludeSpamTrash(true)
+			}
+
+			retry := newRetryBackOff()
+			r, err := backoff.Retry(ctx, func() (*gmail.ListMessagesResponse, error) {
+				if err := awaitRequestSlot(ctx); err != nil {
+					return nil, backoff.Permanent(err)
+				}
+				stats.quota.Add(quotaMessagesList)
+				r, err := req.Do()
+				if err != nil {
+					return nil, retry.classify(err)
+				}
+				return r, nil
+			}, backoff.WithBackOff(retry))
+			if err != nil {
+				return 0, fmt.Errorf("unable to estimate %s messages: %v", label, err)
+			}
+			total += r.ResultSizeEstimate
+		}
+	}
+	return total, nil
+}
+
+// getAccountSpamCounts fetches every account's daily counts concurrently,
+// each with its own accountRun in the context.
+func getAccountSpamCounts(ctx context.Context, runs []*accountRun, services []*gmail.Service) ([]map[string]int, error) {
+	accountCounts := make([]map[string]int, len(services))
+	errs := make([]error, len(services))
+	var wg sync.WaitGroup
+	for i, srv := range services {
+		run := runs[i]
+		wg.Go(func() {
+			messages, err := collectMessages(withAccount(ctx, run), srv)
+			if err != nil {
+				errs[i] = err
+				return
+			}
+			for _, m := range messages {
+				if messageTimeMs(m) <= 0 {
+					run.undated++
+				}
+			}
+			accountCounts[i] = countMessagesByDate(messages)
+		})
+	}
+	wg.Wait()
+
+	for i, err := range errs {
+		if err != nil {
+			return nil, fmt.Errorf("%s: %v", runs[i].name, err)
+		}
+	}
+	return accountCounts, nil
+}
+
+// printAccountSummary prints each day's counts stacked by account, then each
+// account's total and the grand total on their own lines.
+func printAccountSummary(accounts []string, accountCounts []map[string]int) {
+	days := make(map[string]bool)
+	for _, counts := range accountCounts {
+		for date := range counts {
+			days[date] = true
+		}
+	}
+	var dates []string
+	for date := range days {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	fmt.Println()
+	fmt.Println("Per-account breakdown:")
+	fmt.Printf("%-14s", "Date")
+	for _, account := range accounts {
+		fmt.Printf(" %s", account)
+	}
+	fmt.Println()
+
+	for _, date := range dates {
+		dateValue, err := time.Parse("2006-01-02", date)
+		if err != nil {
+			log.Printf("Error parsing date: %v", err)
+			continue
+		}
+		fmt.Printf("%s %s", dateValue.Format("Mon"), date)
+		for i, account := range accounts {
+			fmt.Printf(" %*d", len(account), accountCounts[i][date])
+		}
+		fmt.Println()
+	}
+
+	fmt.Println()
+	grandTotal := 0
+	for i, account := range accounts {
+		total := countsTotal(accountCounts[i])
+		grandTotal += total
+		fmt.Printf("Account total for %s: %d\n", account, total)
+	}
+	fmt.Printf("Grand total across %d accounts: %d\n", len(accounts), grandTotal)
+}
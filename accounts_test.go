@@ -0,0 +1,164 @@
+package main
+
+import (
+	"context"
+	"strings"
+	"sync/atomic"
+	"testing"
+
+	"golang.org/x/oauth2"
+	"google.golang.org/api/gmail/v1"
+)
+
+func TestGetAccountSpamCountsSharesLimiter(t *testing.T) {
+	setFetchTestFlags(t)
+	setTestWindow(t)
+	oldLabels, oldLimiter := labels, sharedFetchLimiter
+	t.Cleanup(func() { labels, sharedFetchLimiter = oldLabels, oldLimiter })
+	labels = stringList{"SPAM"}
+	sharedFetchLimiter = make(chan struct{}, 1)
+
+	// Both accounts' fetches count against the one slot
+	var inFlight, peak atomic.Int64
+	onGet := func(string) {
+		n := inFlight.Add(1)
+		defer inFlight.Add(-1)
+		for {
+			p := peak.Load()
+			if n <= p || peak.CompareAndSwap(p, n) {
+				break
+			}
+		}
+	}
+	services := []*gmail.Service{
+		newFakeGmailService(t, &fakeGmail{pages: [][]string{{"a", "b", "c"}}, onGet: onGet}),
+		newFakeGmailService(t, &fakeGmail{pages: [][]string{{"d"}, {"e"}}, onGet: onGet}),
+	}
+
+	runs := []*accountRun{{name: "alice@example.com"}, {name: "bob@example.com"}}
+	accountCounts, err := getAccountSpamCounts(context.Background(), runs, services)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if got := accountCounts[0]["2024-03-01"]; got != 3 {
+		t.Errorf("alice's count = %d, want 3", got)
+	}
+	if got := accountCounts[1]["2024-03-01"]; got != 2 {
+		t.Errorf("bob's count = %d, want 2", got)
+	}
+	if got := peak.Load(); got > 1 {
+		t.Errorf("%d fetches ran at once across accounts, want at most 1", got)
+	}
+}
+
+func TestGetAccountSpamCountsKeepsAccountsApart(t *testing.T) {
+	setFetchTestFlags(t)
+	setTestWindow(t)
+	oldLabels, oldLimiter := labels, sharedFetchLimiter
+	t.Cleanup(func() { labels, sharedFetchLimiter = oldLabels, oldLimiter })
+	labels = stringList{"SPAM"}
+	sharedFetchLimiter = nil
+
+	// The same IDs name different messages in each mailbox, none of which
+	// has an internalDate
+	services := []*gmail.Service{
+		newFakeGmailService(t, &fakeGmail{pages: [][]string{{"a", "b", "c"}}, undated: true}),
+		newFakeGmailService(t, &fakeGmail{pages: [][]string{{"a", "b"}}, undated: true}),
+	}
+	undatedBefore := undatedCount.Load()
+
+	runs := []*accountRun{{name: "alice@example.com"}, {name: "bob@example.com"}}
+	accountCounts, err := getAccountSpamCounts(context.Background(), runs, services)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if countsTotal(accountCounts[0]) != 0 || countsTotal(accountCounts[1]) != 0 {
+		t.Errorf("counted undated messages: %v", accountCounts)
+	}
+	for i, want := range []int64{3, 2} {
+		if got := runs[i].stats.listed.Load(); got != want {
+			t.Errorf("%s listed %d, want %d", runs[i].name, got, want)
+		}
+		if got := runs[i].stats.fetched.Load(); got != want {
+			t.Errorf("%s fetched %d, want %d", runs[i].name, got, want)
+		}
+	}
+	if runs[0].undated != 3 || runs[1].undated != 2 {
+		t.Errorf("undated = %d and %d, want 3 and 2", runs[0].undated, runs[1].undated)
+	}
+	if got := undatedCount.Load() - undatedBefore; got != 5 {
+		t.Errorf("run counted %d undated messages, want 5 with the shared IDs counted in each account", got)
+	}
+}
+
+func TestReconcileAccount(t *testing.T) {
+	var buf strings.Builder
+	oldStatus := status
+	t.Cleanup(func() { status = oldStatus })
+	status = &buf
+
+	var s runStats
+	s.listed.Add(5)
+	s.fetched.Add(4)
+	s.failures.Add(1)
+	reconcile("bob@example.com: ", &s, 3, 1, 0)
+	want := "bob@example.com: Listed 5, fetched 4, counted 3 (1 with invalid or missing dates, 1 fetch failures).\n"
+	if got := buf.String(); got != want {
+		t.Errorf("reconcile printed %q, want %q", got, want)
+	}
+}
+
+func TestEstimateAccounts(t *testing.T) {
+	setFetchTestFlags(t)
+	setTestWindow(t)
+	oldLabels := labels
+	t.Cleanup(func() { labels = oldLabels })
+	labels = stringList{"SPAM", "TRASH"}
+
+	alice := &fakeGmail{pages: [][]string{{"a"}}, estimate: 30000}
+	bob := &fakeGmail{pages: [][]string{{"b"}}, estimate: 25000}
+	services := []*gmail.Service{newFakeGmailService(t, alice), newFakeGmailService(t, bob)}
+
+	got, err := estimateAccounts(context.Background(), services)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if got != 110000 {
+		t.Errorf("estimateAccounts = %d, want 110000 across both labels of both accounts", got)
+	}
+	if alice.gets.Load() != 0 || bob.gets.Load() != 0 {
+		t.Error("estimating fetched messages")
+	}
+}
+
+func TestPrintAccountSummary(t *testing.T) {
+	got := captureStdout(t, func() {
+		printAccountSummary([]string{"alice@example.com", "bob@example.com"}, []map[string]int{
+			{"2024-03-01": 3, "2024-03-02": 1},
+			{"2024-03-02": 12},
+		})
+	})
+	want := `
+Per-account breakdown:
+Date           alice@example.com bob@example.com
+Fri 2024-03-01                 3               0
+Sat 2024-03-02                 1              12
+
+Account total for alice@example.com: 4
+Account total for bob@example.com: 12
+Grand total across 2 accounts: 16
+`
+	if got != want {
+		t.Errorf("printAccountSummary printed:\n%s\nwant:\n%s", got, want)
+	}
+}
+
+func TestAccountTokenPath(t *testing.T) {
+	config := &oauth2.Config{Scopes: []string{gmail.GmailReadonlyScope}}
+	if got := accountTokenPath(config, "alice@example.com"); got != "token-alice@example.com.json" {
+		t.Errorf("accountTokenPath = %q, want token-alice@example.com.json", got)
+	}
+	if got := accountTokenPath(config, "../bob"); got != "token-.._bob.json" {
+		t.Errorf("accountTokenPath = %q, want token-.._bob.json", got)
+	}
+}
@@ -0,0 +1,112 @@
+package main
+
+import (
+	"context"
+	"errors"
+	"flag"
+	"log"
+	"net/http"
+	"sync"
+	"time"
+
+	"google.golang.org/api/googleapi"
+)
+
+var adaptive = flag.Bool("adaptive", false, "halve fetch concurrency when Gmail returns 429s and grow it back as fetches succeed, up to -workers (or 64)")
+
+// adaptiveCeiling is the concurrency -adaptive starts at and recovers to
+// when -workers is unlimited.
+const adaptiveCeiling = 64
+
+// adaptiveCooldown is how long after a cut further 429s are put down to the
+// same burst rather than sustained throttling.
+const adaptiveCooldown = 2 * time.Second
+
+// fetchAIMD is the -adaptive controller shared by every message fetch, or
+// nil when -adaptive is off.
+var fetchAIMD *aimdLimiter
+
+// aimdLimiter is a semaphore whose size follows additive-increase,
+// multiplicative-decrease: it halves (to a floor of 1) on rate limiting and
+// grows by one after each limit's worth of successful fetches.
+type aimdLimiter struct {
+	mu        sync.Mutex
+	limit     int
+	ceiling   int
+	inUse     int
+	successes int
+	lastCut   time.Time
+	// changed is closed and replaced whenever a slot may have opened up
+	changed chan struct{}
+}
+
+func newAIMDLimiter(ceiling int) *aimdLimiter {
+	return &aimdLimiter{limit: ceiling, ceiling: ceiling, changed: make(chan struct{})}
+}
+
+func (l *aimdLimiter) acquire(ctx context.Context) error {
+	for {
+		l.mu.Lock()
+		if l.inUse < l.limit {
+			l.inUse++
+			l.mu.Unlock()
+			return nil
+		}
+		wait := l.changed
+		l.mu.Unlock()
+
+		select {
+		case <-wait:
+		case <-ctx.Done():
+			return ctx.Err()
+		}
+	}
+}
+
+func (l *aimdLimiter) release() {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	l.inUse--
+	l.notify()
+}
+
+// throttled records a 429 and halves the limit unless it was just cut.
+func (l *aimdLimiter) throttled() {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	l.successes = 0
+	if l.limit == 1 || time.Since(l.lastCut) < adaptiveCooldown {
+		return
+	}
+	l.limit = max(1, l.limit/2)
+	l.lastCut = time.Now()
+	log.Printf("Rate limited by Gmail: reducing fetch concurrency to %d", l.limit)
+}
+
+// succeeded records a successful fetch, growing the limit by one after a
+// full limit's worth of them.
+func (l *aimdLimiter) succeeded() {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	if l.limit >= l.ceiling {
+		return
+	}
+	if l.successes++; l.successes >= l.limit {
+		l.limit++
+		l.successes = 0
+		log.Printf("Fetches succeeding: raising fetch concurrency to %d", l.limit)
+		l.notify()
+	}
+}
+
+// notify wakes every acquire waiting for a slot. Callers hold l.mu.
+func (l *aimdLimiter) notify() {
+	close(l.changed)
+	l.changed = make(chan struct{})
+}
+
+// isRateLimited reports whether err is an HTTP 429 from Gmail.
+func isRateLimited(err error) bool {
+	var apiErr *googleapi.Error
+	return errors.As(err, &apiErr) && apiErr.Code == http.StatusTooManyRequests
+}
@@ -0,0 +1,54 @@
+package main
+
+import (
+	"bufio"
+	"flag"
+	"fmt"
+	"os"
+	"strings"
+	"sync/atomic"
+
+	"google.golang.org/api/gmail/v1"
+)
+
+var allowlistPath = flag.String("allowlist", "", "file of known-good senders to leave out of the counts: one address or @domain per line, # starts a comment")
+
+// allowedAddresses and allowedDomains hold the lower-cased -allowlist entries.
+var allowedAddresses = make(map[string]bool)
+var allowedDomains = make(map[string]bool)
+
+// allowlisted counts the messages excluded by -allowlist.
+var allowlisted atomic.Int64
+
+// loadAllowlist reads the -allowlist file.
+func loadAllowlist(path string) error {
+	f, err := os.Open(path)
+	if err != nil {
+		return fmt.Errorf("unable to read allowlist: %v", err)
+	}
+	defer f.Close()
+
+	scanner := bufio.NewScanner(f)
+	for scanner.Scan() {
+		entry, _, _ := strings.Cut(scanner.Text(), "#")
+		entry = strings.ToLower(strings.TrimSpace(entry))
+		switch {
+		case entry == "":
+		case strings.HasPrefix(entry, "@"):
+			allowedDomains[entry[1:]] = true
+		default:
+			allowedAddresses[entry] = true
+		}
+	}
+	if err := scanner.Err(); err != nil {
+		return fmt.Errorf("unable to read allowlist: %v", err)
+	}
+	return nil
+}
+
+// isAllowlisted reports whether the message's sender matches an -allowlist
+// address or domain.
+func isAllowlisted(m *gmail.Message) bool {
+	sender := messageSender(m)
+	return allowedAddresses[sender] || allowedDomains[senderDomain(sender)]
+}
@@ -0,0 +1,178 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"math"
+	"sort"
+	"time"
+)
+
+var showPercentiles = flag.Bool("percentiles", false, "print the mean and p50/p90/p99 of daily counts over the window, counting days without spam as zero")
+
+// windowDates returns every date in the query window, oldest first.
+func windowDates() []string {
+	start, err := time.ParseInLocation("2006-01-02", cutoffDate, location)
+	if err != nil {
+		return nil
+	}
+
+	var end time.Time
+	if endDate != "" {
+		end, err = time.ParseInLocation("2006-01-02", endDate, location)
+		if err != nil {
+			return nil
+		}
+	} else {
+		// The window runs through the end of today
+		now := time.Now().In(location)
+		end = time.Date(now.Year(), now.Month(), now.Day(), 0, 0, 0, 0, location).AddDate(0, 0, 1)
+	}
+
+	var dates []string
+	for day := start; day.Before(end); day = day.AddDate(0, 0, 1) {
+		dates = append(dates, day.Format("2006-01-02"))
+	}
+	return dates
+}
+
+// dailySeries returns the count for every date in the query window, with
+// zero for days without spam.
+func dailySeries(spamCounts map[string]int) []int {
+	var series []int
+	for _, date := range windowDates() {
+		series = append(series, spamCounts[date])
+	}
+	return series
+}
+
+// percentile returns the p-th percentile (0-100) of sorted values, linearly
+// interpolating between the two closest ranks. This is the same method as
+// NumPy's default and Excel's PERCENTILE.INC.
+func percentile(sorted []int, p float64) float64 {
+	if len(sorted) == 0 {
+		return 0
+	}
+	rank := p / 100 * float64(len(sorted)-1)
+	lo, hi := int(math.Floor(rank)), int(math.Ceil(rank))
+	return float64(sorted[lo]) + (rank-float64(lo))*float64(sorted[hi]-sorted[lo])
+}
+
+// dailyStats summarizes the distribution of daily counts over the window.
+type dailyStats struct {
+	Mean float64 `json:"mean"`
+	P50  float64 `json:"p50"`
+	P90  float64 `json:"p90"`
+	P99  float64 `json:"p99"`
+}
+
+func computeDailyStats(spamCounts map[string]int) dailyStats {
+	series := dailySeries(spamCounts)
+	sort.Ints(series)
+
+	total := 0
+	for _, count := range series {
+		total += count
+	}
+	mean := 0.0
+	if len(series) > 0 {
+		mean = float64(total) / float64(len(series))
+	}
+
+	return dailyStats{
+		Mean: roundTo2(mean),
+		P50:  roundTo2(percentile(series, 50)),
+		P90:  roundTo2(percentile(series, 90)),
+		P99:  roundTo2(percentile(series, 99)),
+	}
+}
+
+// roundTo2 rounds to two decimal places for display and JSON.
+func roundTo2(value float64) float64 {
+	return math.Round(value*100) / 100
+}
+
+func printDailyStats(spamCounts map[string]int) {
+	s := computeDailyStats(spamCounts)
+	fmt.Printf("Daily mean: %.2f, p50: %.2f, p90: %.2f, p99: %.2f (linear interpolation)\n", s.Mean, s.P50, s.P90, s.P99)
+}
+
+// spamFreeStreak is the longest run of consecutive days in the window with
+// no spam. Start and End are empty when every day had spam.
+type spamFreeStreak struct {
+	Days  int    `json:"days"`
+	Start string `json:"start,omitempty"`
+	End   string `json:"end,omitempty"`
+}
+
+// longestSpamFreeStreak finds the longest run of zero days in the window,
+// preferring the earliest run on ties.
+func longestSpamFreeStreak(spamCounts map[string]int) spamFreeStreak {
+	var best spamFreeStreak
+	run, runStart := 0, ""
+	for _, date := range windowDates() {
+		if spamCounts[date] > 0 {
+			run = 0
+			continue
+		}
+		if run == 0 {
+			runStart = date
+		}
+		run++
+		if run > best.Days {
+			best = spamFreeStreak{Days: run, Start: runStart, End: date}
+		}
+	}
+	return best
+}
+
+func printSpamFreeStreak(spamCounts map[string]int) {
+	streak := longestSpamFreeStreak(spamCounts)
+	switch streak.Days {
+	case 0:
+		fmt.Println("Longest spam-free streak in the window: 0 days")
+	case 1:
+		fmt.Printf("Longest spam-free streak in the window: 1 day (%s)\n", streak.Start)
+	default:
+		fmt.Printf("Longest spam-free streak in the window: %d days (%s to %s)\n", streak.Days, streak.Start, streak.End)
+	}
+}
+
+var sparkline = flag.Bool("sparkline", false, "print the zero-filled daily counts as a one-line sparkline after the summary")
+var asciiOnly = flag.Bool("ascii", false, "draw -sparkline with ASCII characters for terminals without Unicode")
+
+var sparkLevels = []rune("▁▂▃▄▅▆▇█")
+var asciiSparkLevels = []rune("_.-=+*#@")
+
+// renderSparkline scales series to the highest count, so the busiest day
+// gets the top level and a day without spam the bottom one. When every day
+// has the same count the line is flat: at the top if there was any spam, at
+// the bottom if there was none.
+func renderSparkline(series []int, levels []rune) string {
+	peak := 0
+	for _, count := range series {
+		peak = max(peak, count)
+	}
+
+	line := make([]rune, len(series))
+	for i, count := range series {
+		level := 0
+		if peak > 0 {
+			level = count * (len(levels) - 1) / peak
+		}
+		line[i] = levels[level]
+	}
+	return string(line)
+}
+
+func printSparkline(spamCounts map[string]int) {
+	levels := sparkLevels
+	if *asciiOnly {
+		levels = asciiSparkLevels
+	}
+	series := dailySeries(spamCounts)
+	if len(series) == 0 {
+		return
+	}
+	fmt.Printf("Trend: %s\n", renderSparkline(series, levels))
+}
@@ -0,0 +1,76 @@
+package main
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"log"
+	"os"
+	"sort"
+	"time"
+)
+
+var baselinePath = flag.String("baseline", "", "compare each day's count against a report previously saved with -format json or -json-out")
+
+// baselineCounts holds the daily counts loaded from -baseline.
+var baselineCounts map[string]int
+
+// loadBaseline reads the counts from a saved JSON report.
+func loadBaseline(path string) error {
+	b, err := os.ReadFile(path)
+	if err != nil {
+		return fmt.Errorf("unable to read baseline %s: %v", path, err)
+	}
+	var report jsonReport
+	if err := json.Unmarshal(b, &report); err != nil {
+		return fmt.Errorf("unable to parse baseline %s: %v", path, err)
+	}
+	if report.Counts == nil {
+		return fmt.Errorf("baseline %s has no counts", path)
+	}
+	baselineCounts = report.Counts
+	return nil
+}
+
+// printBaselineComparison prints each day with its change from the baseline.
+// Days only in the current run are marked new, and days only in the baseline
+// are shown as zero and marked removed.
+func printBaselineComparison(spamCounts map[string]int) {
+	dateSet := make(map[string]bool)
+	for date := range spamCounts {
+		dateSet[date] = true
+	}
+	for date := range baselineCounts {
+		dateSet[date] = true
+	}
+	var dates []string
+	for date := range dateSet {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	total, baselineTotal := 0, 0
+	for _, date := range dates {
+		dateValue, err := time.Parse("2006-01-02", date)
+		if err != nil {
+			log.Printf("Error parsing date: %v", err)
+			continue
+		}
+		count, inCurrent := spamCounts[date]
+		was, inBaseline := baselineCounts[date]
+		total += count
+		baselineTotal += was
+
+		var change string
+		switch {
+		case !inBaseline:
+			change = "new"
+		case !inCurrent:
+			change = fmt.Sprintf("removed, was %d", was)
+		default:
+			change = fmt.Sprintf("%+d", count-was)
+		}
+		fmt.Printf("%s %s %d (%s)\n", dateValue.Format("Mon"), displayDate(dateValue), count, change)
+	}
+	fmt.Printf("Total: %d (%+d)\n", total, total-baselineTotal)
+}
@@ -0,0 +1,222 @@
+package main
+
+import (
+	"context"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"log"
+	"os"
+	"slices"
+	"sync"
+	"sync/atomic"
+	"time"
+
+	"google.golang.org/api/gmail/v1"
+)
+
+// checkpoint records how far a -checkpoint run got. Counts only ever include
+// pages whose messages were all fetched, and PageToken points at the first
+// page that was not, so resuming never counts a page twice.
+//
+// Paging can repeat an ID on consecutive pages, so LastPage keeps digests of
+// the last counted page's IDs (see idDigest) rather than every ID seen: the
+// file stays small and is rewritten cheaply after each page, and it holds no
+// raw message IDs. A message on several labels is counted under the first one
+// it carries (see getCheckpointedSpamCounts).
+type checkpoint struct {
+	Query      string         `json:"query"`
+	Labels     []string       `json:"labels"`
+	LabelIndex int            `json:"label_index"`
+	PageToken  string         `json:"page_token"`
+	Counts     map[string]int `json:"daily_counts"`
+	LastPage   []string       `json:"last_page_digests"`
+}
+
+// idDigest returns the short hash of a message ID stored in a checkpoint.
+func idDigest(id string) string {
+	sum := sha256.Sum256([]byte(id))
+	return hex.EncodeToString(sum[:8])
+}
+
+// loadCheckpoint reads the checkpoint at path. A missing file, or one written
+// for a different query or label set, yields a fresh checkpoint.
+func loadCheckpoint(path, query string) (*checkpoint, error) {
+	fresh := &checkpoint{
+		Query:  query,
+		Labels: labels,
+		Counts: make(map[string]int),
+	}
+
+	b, err := os.ReadFile(path)
+	if errors.Is(err, os.ErrNotExist) {
+		return fresh, nil
+	}
+	if err != nil {
+		return nil, fmt.Errorf("unable to read checkpoint %s: %v", path, err)
+	}
+
+	cp := &checkpoint{}
+	if err := json.Unmarshal(b, cp); err != nil {
+		return nil, fmt.Errorf("unable to parse checkpoint %s: %v", path, err)
+	}
+	if cp.Query != query || fmt.Sprint(cp.Labels) != fmt.Sprint([]string(labels)) {
+		log.Printf("Checkpoint %s was written for a different query; starting over", path)
+		return fresh, nil
+	}
+	if cp.Counts == nil {
+		cp.Counts = make(map[string]int)
+	}
+
+	log.Printf("Resuming from checkpoint %s", path)
+	return cp, nil
+}
+
+// save writes the checkpoint to a temporary file and renames it into place
+// so an interrupted write never leaves a truncated checkpoint behind.
+func (cp *checkpoint) save(path string) error {
+	b, err := json.Marshal(cp)
+	if err != nil {
+		return err
+	}
+	tmp := path + ".tmp"
+	if err := os.WriteFile(tmp, b, 0600); err != nil {
+		return err
+	}
+	return os.Rename(tmp, path)
+}
+
+// getCheckpointedSpamCounts is like getSpamCounts, but walks the pages one at
+// a time, saving a checkpoint after each completed page. A run that times out
+// or is interrupted picks up from the last saved page; a completed run
+// removes the checkpoint.
+func getCheckpointedSpamCounts(ctx context.Context, srv *gmail.Service, path string) (map[string]int, error) {
+	query := buildQuery()
+	cp, err := loadCheckpoint(path, query)
+	if err != nil {
+		return nil, err
+	}
+
+	ctx, cancel := context.WithTimeout(ctx, time.Duration(*timeout)*time.Second)
+	defer cancel()
+
+	limiter := newFetchLimiter()
+	pages := 0
+	for cp.LabelIndex < len(labels) {
+		label := labels[cp.LabelIndex]
+		fmt.Fprintf(status, "Gmail query: %s\n", describeQuery(label))
+
+		for {
+			r, err := listPage(ctx, srv, label, query, cp.PageToken)
+			if err != nil {
+				if ctx.Err() != nil {
+					return nil, fmt.Errorf("timed out waiting for messages; rerun to resume from %s", path)
+				}
+				return nil, err
+			}
+			if pages == 0 {
+				stats.estimated.Add(r.ResultSizeEstimate)
+				if err := confirmLargeRun(r.ResultSizeEstimate); err != nil {
+					return nil, err
+				}
+			}
+
+			// Fetch the whole page before counting any of it
+			fetched := make([]*gmail.Message, len(r.Messages))
+			var failed atomic.Int64
+			var wg sync.WaitGroup
+			for i, msg := range r.Messages {
+				messageId := msg.Id
+				wg.Go(func() {
+					fullMsg, err := fetchMessage(ctx, srv, messageId, limiter)
+					if err != nil {
+						failed.Add(1)
+						if *debug {
+							log.Printf("Error fetching message %s: %v", redactID(messageId), err)
+						}
+						return
+					}
+					fetched[i] = fullMsg
+				})
+			}
+			wg.Wait()
+			if ctx.Err() != nil {
+				return nil, fmt.Errorf("timed out waiting for messages; rerun to resume from %s", path)
+			}
+			if n := failed.Load(); n > 0 {
+				// Counting the rest would move PageToken past messages that
+				// were never counted, losing them from every resumed run
+				return nil, fmt.Errorf("unable to fetch %d messages on page %d; rerun to resume from %s", n, pages+1, path)
+			}
+
+			previous := make(map[string]bool, len(cp.LastPage))
+			for _, digest := range cp.LastPage {
+				previous[digest] = true
+			}
+			var page []string
+			for _, m := range fetched {
+				if m == nil {
+					continue
+				}
+				digest := idDigest(m.Id)
+				if previous[digest] {
+					stats.duplicates.Add(1)
+					continue
+				}
+				previous[digest] = true
+				page = append(page, digest)
+				if hasAnyLabel(m, labels[:cp.LabelIndex]) {
+					// Already counted when an earlier label was walked
+					stats.duplicates.Add(1)
+					continue
+				}
+				if !keepMessage(m) {
+					continue
+				}
+				if emailDate, ok := messageDate(m); ok {
+					cp.Counts[emailDate]++
+				}
+			}
+
+			pages++
+			cp.LastPage = page
+			cp.PageToken = r.NextPageToken
+			if cp.PageToken == "" {
+				break
+			}
+			if err := cp.save(path); err != nil {
+				return nil, fmt.Errorf("unable to save checkpoint %s: %v", path, err)
+			}
+			if *maxPages > 0 && pages >= *maxPages {
+				recordWarning()
+				log.Printf("Reached the -max-pages limit of %d pages; rerun to resume from %s", *maxPages, path)
+				return cp.Counts, nil
+			}
+		}
+
+		// Record that this label is finished before moving to the next one
+		cp.LabelIndex++
+		cp.LastPage = nil
+		if err := cp.save(path); err != nil {
+			return nil, fmt.Errorf("unable to save checkpoint %s: %v", path, err)
+		}
+	}
+
+	if err := os.Remove(path); err != nil && !errors.Is(err, os.ErrNotExist) {
+		log.Printf("Unable to remove checkpoint %s: %v", path, err)
+	}
+
+	return cp.Counts, nil
+}
+
+// hasAnyLabel reports whether m carries any of the label IDs in ids.
+func hasAnyLabel(m *gmail.Message, ids []string) bool {
+	for _, id := range m.LabelIds {
+		if slices.Contains(ids, id) {
+			return true
+		}
+	}
+	return false
+}
@@ -0,0 +1,59 @@
+package main
+
+import (
+	"context"
+	"encoding/json"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestCheckpointResumeAcrossRepeatedPageBoundary(t *testing.T) {
+	setFetchTestFlags(t)
+	setTestWindow(t)
+	oldLabels, oldMaxPages := labels, *maxPages
+	t.Cleanup(func() { labels, *maxPages = oldLabels, oldMaxPages })
+	labels = stringList{"SPAM"}
+
+	// "b" is listed again at the top of the second page
+	srv := newFakeGmailService(t, &fakeGmail{pages: [][]string{{"a", "b"}, {"b", "c"}}})
+	path := filepath.Join(t.TempDir(), "checkpoint.json")
+
+	// Stop after the first page, as an interrupted run would
+	*maxPages = 1
+	counts, err := getCheckpointedSpamCounts(context.Background(), srv, path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if got := counts["2024-03-01"]; got != 2 {
+		t.Errorf("first run counted %d, want 2", got)
+	}
+
+	b, err := os.ReadFile(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if strings.Contains(string(b), `"a"`) || strings.Contains(string(b), `"b"`) {
+		t.Errorf("checkpoint holds raw message IDs: %s", b)
+	}
+	var cp checkpoint
+	if err := json.Unmarshal(b, &cp); err != nil {
+		t.Fatal(err)
+	}
+	if len(cp.LastPage) != 2 {
+		t.Errorf("checkpoint keeps %d digests, want the 2 from the last page", len(cp.LastPage))
+	}
+
+	*maxPages = 0
+	counts, err = getCheckpointedSpamCounts(context.Background(), srv, path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if got := counts["2024-03-01"]; got != 3 {
+		t.Errorf("resumed run counted %d in total, want 3 with the repeated b counted once", got)
+	}
+	if _, err := os.Stat(path); !os.IsNotExist(err) {
+		t.Errorf("checkpoint not removed after a completed run: %v", err)
+	}
+}
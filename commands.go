@@ -0,0 +1,181 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"maps"
+	"os"
+	"slices"
+	"sort"
+	"strings"
+)
+
+// authCheck is set by the auth-check subcommand.
+var authCheck bool
+
+// globalFlags apply to every subcommand: where the credentials and token
+// live, how Gmail is reached, the timezone and what gets logged.
+var globalFlags = []string{
+	"credentials", "keyring", "no-interactive", "no-token-persist", "auth-timeout",
+	"proxy", "mailbox", "timeout", "request-timeout", "rps",
+	"timezone", "utc", "debug", "redact", "dump-raw", "stats", "status-file", "strict",
+}
+
+// selectFlags pick the messages a subcommand works on.
+var selectFlags = []string{
+	"days", "all", "as-of", "business-days", "last-month", "last-week", "this-month", "today", "within",
+	"label", "search-all", "query", "has-attachment", "important", "starred", "emails-only",
+	"subject-regex", "allowlist", "min-size", "date-source",
+}
+
+// fetchFlags tune how the selected messages are listed and fetched.
+var fetchFlags = []string{
+	"workers", "adaptive", "page-size", "max-pages", "initial-delay", "early-exit", "retry-on-empty",
+	"deadline", "warmup", "heartbeat", "confirm-above", "yes",
+}
+
+// subcommands maps each subcommand to the flags it stands for and the flags
+// it accepts besides globalFlags; a nil list accepts every flag. Running with
+// no subcommand is the same as "count", so existing invocations keep working.
+var subcommands = map[string]struct {
+	help  string
+	set   func()
+	flags []string
+}{
+	"count":      {"count spam by day, or per the mode flags (the default)", func() {}, nil},
+	"explain":    {"describe what count would do (same as -explain)", func() { *explain = true }, nil},
+	"list-ids":   {"count spam by List-Id header (same as -by-list-id)", func() { *byListID = true }, slices.Concat(selectFlags, fetchFlags, []string{"by-list-id", "format", "csv-bom"})},
+	"delete":     {"move the selected spam to Trash (same as -delete)", func() { *deleteSpam = true }, slices.Concat(selectFlags, fetchFlags, []string{"delete", "dry-run"})},
+	"query":      {"print the Gmail query (same as -print-query)", func() { *printQuery = true }, slices.Concat(selectFlags, []string{"print-query"})},
+	"labels":     {"list the mailbox's labels (same as -list-labels)", func() { *listLabels = true }, []string{"list-labels", "warmup"}},
+	"diagnose":   {"run connectivity and quota checks (same as -diagnose)", func() { *diagnose = true }, []string{"diagnose"}},
+	"auth-check": {"check that the cached token works, without starting the consent flow", func() { authCheck, *noInteractive = true, true }, []string{"accounts"}},
+}
+
+// commandLineFlags names the flags given on the command line, on either side
+// of the subcommand.
+var commandLineFlags = make(map[string]bool)
+
+// subcommandAccepts reports whether the named flag applies to command.
+func subcommandAccepts(command, name string) bool {
+	flags := subcommands[command].flags
+	return flags == nil || slices.Contains(globalFlags, name) || slices.Contains(flags, name)
+}
+
+// subcommandFlagSet returns a FlagSet holding just the flags command
+// accepts. They share their values with the top-level flags, so parsing it
+// sets the same variables.
+func subcommandFlagSet(command string) *flag.FlagSet {
+	fs := flag.NewFlagSet(command, flag.ExitOnError)
+	flag.VisitAll(func(f *flag.Flag) {
+		if subcommandAccepts(command, f.Name) {
+			fs.Var(f.Value, f.Name, f.Usage)
+		}
+	})
+	fs.Usage = func() {
+		fmt.Fprintf(fs.Output(), "Usage: %s [global flags] %s [flags]\n\n%s\n\nFlags:\n", os.Args[0], command, subcommands[command].help)
+		fs.PrintDefaults()
+	}
+	return fs
+}
+
+// parseCommandLine parses the flags on either side of an optional
+// subcommand and applies it. Flags after the subcommand are parsed with its
+// own FlagSet, and any flag that does not apply to it, on either side, is
+// rejected. It returns the subcommand, or "" if none was given.
+func parseCommandLine() (string, error) {
+	flag.Usage = usage
+	flag.Parse()
+	flag.Visit(func(f *flag.Flag) {
+		commandLineFlags[f.Name] = true
+	})
+
+	command := ""
+	fs := flag.CommandLine
+	if flag.NArg() > 0 {
+		command = flag.Arg(0)
+		if _, ok := subcommands[command]; !ok {
+			return "", fmt.Errorf("unknown subcommand %q", command)
+		}
+		fs = subcommandFlagSet(command)
+		// Exits on error, as flag.Parse does
+		fs.Parse(flag.Args()[1:])
+		if fs.NArg() > 0 {
+			return "", fmt.Errorf("unexpected argument %q after %s", fs.Arg(0), command)
+		}
+		fs.Visit(func(f *flag.Flag) {
+			commandLineFlags[f.Name] = true
+		})
+		var names []string
+		for name := range commandLineFlags {
+			if !subcommandAccepts(command, name) {
+				names = append(names, "-"+name)
+			}
+		}
+		if len(names) > 0 {
+			sort.Strings(names)
+			return "", fmt.Errorf("%s does not apply to the %s subcommand (see %s %s -h)", strings.Join(names, ", "), command, os.Args[0], command)
+		}
+	}
+
+	// Only after the whole command line is parsed, so it takes precedence.
+	// A subcommand only picks up environment variables for its own flags.
+	if err := applyEnvFlags(fs, commandLineFlags); err != nil {
+		return "", err
+	}
+	if command != "" {
+		subcommands[command].set()
+	}
+	return command, nil
+}
+
+// envFlagName returns the environment variable that sets a flag: GCS_ and
+// the flag name upper-cased with dashes as underscores, e.g. GCS_REQUEST_TIMEOUT.
+func envFlagName(name string) string {
+	return "GCS_" + strings.ToUpper(strings.ReplaceAll(name, "-", "_"))
+}
+
+// applyEnvFlags sets each flag of fs that was not given on the command line
+// (parsed by fs, or named in given) but has its environment variable set, so
+// the command line takes precedence. A repeatable flag such as -label given
+// on the command line ignores its environment variable entirely.
+func applyEnvFlags(fs *flag.FlagSet, given map[string]bool) error {
+	onCommandLine := maps.Clone(given)
+	if onCommandLine == nil {
+		onCommandLine = make(map[string]bool)
+	}
+	fs.Visit(func(f *flag.Flag) {
+		onCommandLine[f.Name] = true
+	})
+
+	var err error
+	fs.VisitAll(func(f *flag.Flag) {
+		if onCommandLine[f.Name] || err != nil {
+			return
+		}
+		value, ok := os.LookupEnv(envFlagName(f.Name))
+		if !ok {
+			return
+		}
+		if setErr := fs.Set(f.Name, value); setErr != nil {
+			err = fmt.Errorf("invalid %s: %v", envFlagName(f.Name), setErr)
+		}
+	})
+	return err
+}
+
+func usage() {
+	out := flag.CommandLine.Output()
+	fmt.Fprintf(out, "Usage: %s [flags] [subcommand] [flags]\n\nSubcommands:\n", os.Args[0])
+	var names []string
+	for name := range subcommands {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+	for _, name := range names {
+		fmt.Fprintf(out, "  %-12s %s\n", name, subcommands[name].help)
+	}
+	fmt.Fprintln(out, "\nSubcommands other than count and explain take only the global flags and their own; run <subcommand> -h to list them.")
+	fmt.Fprintln(out, "\nFlags (each can also be set as GCS_<NAME>, e.g. GCS_DAYS=7; the command line wins):")
+	flag.PrintDefaults()
+}
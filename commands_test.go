@@ -0,0 +1,122 @@
+package main
+
+import (
+	"flag"
+	"io"
+	"testing"
+)
+
+func TestApplyEnvFlags(t *testing.T) {
+	t.Setenv("GCS_TEST_DAYS", "30")
+	t.Setenv("GCS_TEST_TODAY", "true")
+	t.Setenv("GCS_TEST_TIMEOUT", "90")
+	t.Setenv("GCS_TEST_LABEL", "INBOX")
+
+	fs := flag.NewFlagSet("test", flag.ContinueOnError)
+	fs.SetOutput(io.Discard)
+	days := fs.Int("test-days", 7, "")
+	today := fs.Bool("test-today", false, "")
+	timeout := fs.Int("test-timeout", 60, "")
+	label := fs.String("test-label", "SPAM", "")
+	if err := fs.Parse([]string{"-test-days", "3", "-test-timeout", "60"}); err != nil {
+		t.Fatal(err)
+	}
+	// -test-label was given before a subcommand, so fs did not parse it
+	if err := applyEnvFlags(fs, map[string]bool{"test-label": true}); err != nil {
+		t.Fatal(err)
+	}
+
+	if *days != 3 || *timeout != 60 || *label != "SPAM" {
+		t.Errorf("command line values were overridden: days %d, timeout %d, label %s; want 3, 60, SPAM", *days, *timeout, *label)
+	}
+	if !*today {
+		t.Error("GCS_TEST_TODAY was not applied")
+	}
+}
+
+func TestApplyEnvFlagsInvalidValue(t *testing.T) {
+	t.Setenv("GCS_TEST_DAYS", "thirty")
+
+	fs := flag.NewFlagSet("test", flag.ContinueOnError)
+	fs.SetOutput(io.Discard)
+	fs.Int("test-days", 7, "")
+	if err := fs.Parse(nil); err != nil {
+		t.Fatal(err)
+	}
+	if err := applyEnvFlags(fs, nil); err == nil {
+		t.Error("applyEnvFlags accepted GCS_TEST_DAYS=thirty")
+	}
+}
+
+func TestEnvFlagName(t *testing.T) {
+	if got := envFlagName("request-timeout"); got != "GCS_REQUEST_TIMEOUT" {
+		t.Errorf("envFlagName(request-timeout) = %s, want GCS_REQUEST_TIMEOUT", got)
+	}
+}
+
+func TestSubcommandFlagsExist(t *testing.T) {
+	groups := map[string][]string{"globalFlags": globalFlags, "selectFlags": selectFlags, "fetchFlags": fetchFlags}
+	for name, sub := range subcommands {
+		groups[name] = sub.flags
+	}
+	for group, names := range groups {
+		for _, name := range names {
+			if flag.Lookup(name) == nil {
+				t.Errorf("%s names -%s, which is not a flag", group, name)
+			}
+		}
+	}
+}
+
+func TestSubcommandFlagSet(t *testing.T) {
+	tests := []struct {
+		command  string
+		accepted []string
+		rejected []string
+	}{
+		{"count", []string{"days", "by-sender", "credentials", "delete"}, nil},
+		{"labels", []string{"credentials", "timezone", "mailbox"}, []string{"days", "by-sender", "delete", "format"}},
+		{"list-ids", []string{"days", "label", "workers", "format", "credentials"}, []string{"by-sender", "split-labels", "delete", "webhook"}},
+		{"delete", []string{"days", "dry-run", "yes", "credentials"}, []string{"by-sender", "format", "csv-out"}},
+		{"query", []string{"days", "label", "query"}, []string{"workers", "format"}},
+		{"diagnose", []string{"credentials", "rps"}, []string{"days"}},
+	}
+	for _, tt := range tests {
+		fs := subcommandFlagSet(tt.command)
+		for _, name := range tt.accepted {
+			if fs.Lookup(name) == nil || !subcommandAccepts(tt.command, name) {
+				t.Errorf("%s does not accept -%s", tt.command, name)
+			}
+		}
+		for _, name := range tt.rejected {
+			if fs.Lookup(name) != nil || subcommandAccepts(tt.command, name) {
+				t.Errorf("%s accepts -%s", tt.command, name)
+			}
+		}
+	}
+}
+
+func TestListIDsSubcommand(t *testing.T) {
+	old := *byListID
+	t.Cleanup(func() { *byListID = old })
+	*byListID = false
+
+	subcommands["list-ids"].set()
+	if !*byListID {
+		t.Error("list-ids did not select -by-list-id")
+	}
+}
+
+func TestSubcommandFlagSetSharesValues(t *testing.T) {
+	old := *days
+	t.Cleanup(func() { *days = old })
+
+	fs := subcommandFlagSet("query")
+	fs.SetOutput(io.Discard)
+	if err := fs.Parse([]string{"-days", "12"}); err != nil {
+		t.Fatal(err)
+	}
+	if *days != 12 {
+		t.Errorf("-days after query = %d, want 12", *days)
+	}
+}
@@ -0,0 +1,41 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"sort"
+	"time"
+)
+
+var compact = flag.Bool("compact", false, "print a single summary line with the total and the peak day")
+
+// compactSummary formats the -compact line, e.g.
+// "Spam (30d): 412 total, peak Tue 2024-03-12 (58)".
+func compactSummary(spamCounts map[string]int) string {
+	var dates []string
+	for date := range spamCounts {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	total := 0
+	peakDate := ""
+	for _, date := range dates {
+		count := spamCounts[date]
+		total += count
+		// Ties go to the earliest day
+		if peakDate == "" || count > spamCounts[peakDate] {
+			peakDate = date
+		}
+	}
+	if total == 0 {
+		return fmt.Sprintf("Spam (%s): no spam", windowLabel)
+	}
+
+	// Dates are already in the bucketing timezone; parse them as plain calendar days
+	peak := peakDate
+	if peakValue, err := time.Parse("2006-01-02", peakDate); err == nil {
+		peak = peakValue.Format("Mon") + " " + displayDate(peakValue)
+	}
+	return fmt.Sprintf("Spam (%s): %d total, peak %s (%d)", windowLabel, total, peak, spamCounts[peakDate])
+}
@@ -0,0 +1,69 @@
+package main
+
+import (
+	"bufio"
+	"errors"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"strings"
+)
+
+var yes = flag.Bool("yes", false, "answer yes to confirmation prompts; required to proceed past them when stdin is not a terminal")
+var confirmAbove = flag.Int64("confirm-above", 50000, "ask for confirmation when Gmail estimates more than this many matching messages (0 = never ask)")
+
+// largeRunConfirmed remembers that the user already agreed to a large run, so
+// later labels do not ask again.
+var largeRunConfirmed bool
+
+// isTerminal reports whether f is attached to a terminal.
+func isTerminal(f *os.File) bool {
+	info, err := f.Stat()
+	if err != nil {
+		return false
+	}
+	return info.Mode()&os.ModeCharDevice != 0
+}
+
+// confirm asks question on the terminal and reports whether the user answered
+// yes. With -yes it answers yes without asking; when stdin is not a terminal
+// it answers no.
+func confirm(question string) (bool, error) {
+	if *yes {
+		return true, nil
+	}
+	if !isTerminal(os.Stdin) {
+		return false, nil
+	}
+
+	fmt.Fprintf(os.Stderr, "%s [y/N] ", question)
+	answer, err := bufio.NewReader(os.Stdin).ReadString('\n')
+	if err != nil && !errors.Is(err, io.EOF) {
+		return false, err
+	}
+	answer = strings.ToLower(strings.TrimSpace(answer))
+	return answer == "y" || answer == "yes", nil
+}
+
+// confirmLargeRun guards against accidentally fetching a huge mailbox, using
+// the resultSizeEstimate Gmail returns with the first page.
+func confirmLargeRun(estimate int64) error {
+	if *confirmAbove <= 0 || estimate <= *confirmAbove || largeRunConfirmed {
+		return nil
+	}
+
+	ok, err := confirm(fmt.Sprintf("~%d messages, continue?", estimate))
+	if err != nil {
+		return fmt.Errorf("unable to read confirmation: %v", err)
+	}
+	if !ok {
+		if !isTerminal(os.Stdin) {
+			return fmt.Errorf("Gmail estimates ~%d matching messages; rerun with -yes to proceed", estimate)
+		}
+		return fmt.Errorf("aborted: Gmail estimates ~%d matching messages", estimate)
+	}
+
+	largeRunConfirmed = true
+	return nil
+}
@@ -0,0 +1,76 @@
+package main
+
+import (
+	"encoding/csv"
+	"io"
+	"sort"
+	"strconv"
+)
+
+// utf8BOM lets spreadsheet tools such as Excel detect UTF-8 encoded csv.
+const utf8BOM = "\ufeff"
+
+// newCSVWriter returns a csv.Writer on w, writing the BOM first if -csv-bom is set.
+func newCSVWriter(w io.Writer) (*csv.Writer, error) {
+	if *csvBOM {
+		if _, err := io.WriteString(w, utf8BOM); err != nil {
+			return nil, err
+		}
+	}
+	return csv.NewWriter(w), nil
+}
+
+// writeCSVSummary writes the daily counts as csv with a date,count header row.
+func writeCSVSummary(w io.Writer, spamCounts map[string]int) error {
+	cw, err := newCSVWriter(w)
+	if err != nil {
+		return err
+	}
+
+	var dates []string
+	for date := range spamCounts {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	if err := cw.Write([]string{"date", "count"}); err != nil {
+		return err
+	}
+	for _, date := range dates {
+		if err := cw.Write([]string{date, strconv.Itoa(spamCounts[date])}); err != nil {
+			return err
+		}
+	}
+	cw.Flush()
+	return cw.Error()
+}
+
+// writeLabelCSVSummary writes the per-label counts as csv with a date column
+// followed by one column per label.
+func writeLabelCSVSummary(w io.Writer, labelCounts map[string]map[string]int) error {
+	cw, err := newCSVWriter(w)
+	if err != nil {
+		return err
+	}
+
+	var dates []string
+	for date := range labelCounts {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	if err := cw.Write(append([]string{"date"}, labels...)); err != nil {
+		return err
+	}
+	for _, date := range dates {
+		record := []string{date}
+		for _, label := range labels {
+			record = append(record, strconv.Itoa(labelCounts[date][label]))
+		}
+		if err := cw.Write(record); err != nil {
+			return err
+		}
+	}
+	cw.Flush()
+	return cw.Error()
+}
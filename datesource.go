@@ -0,0 +1,71 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"log"
+	"net/mail"
+	"strings"
+
+	"google.golang.org/api/gmail/v1"
+)
+
+// dateSource selects the timestamp messages are bucketed by:
+//   - internal: Gmail's internalDate, when Gmail received the message (the
+//     default, and what after:/before: approximate)
+//   - received: the date on the topmost Received header, i.e. the last hop
+//     before Gmail; usually within seconds of internalDate
+//   - header: the sender's Date header, which spammers often forge, so it can
+//     be far in the past or future. Its own UTC offset is honored before the
+//     date is moved into the report timezone.
+//
+// received and header fall back to internalDate when the header is missing or
+// malformed. Since the query still selects by received date, a message whose
+// header date lies outside the window is dropped rather than counted.
+var dateSource = flag.String("date-source", "internal", "timestamp to bucket messages by: internal (internalDate), received (topmost Received header) or header (Date header)")
+
+func validateDateSource() error {
+	switch *dateSource {
+	case "internal", "received", "header":
+		return nil
+	}
+	return fmt.Errorf("unknown -date-source %q: want internal, received or header", *dateSource)
+}
+
+// dateSourceHeader returns the header -date-source needs fetched, or "".
+func dateSourceHeader() string {
+	switch *dateSource {
+	case "received":
+		return "Received"
+	case "header":
+		return "Date"
+	}
+	return ""
+}
+
+// messageTimeMs returns the message's timestamp from -date-source in epoch
+// milliseconds, falling back to internalDate.
+func messageTimeMs(m *gmail.Message) int64 {
+	name := dateSourceHeader()
+	if name == "" {
+		return m.InternalDate
+	}
+
+	value := messageHeader(m, name)
+	if name == "Received" {
+		// The date follows the last semicolon: "from ... by ...; <date>"
+		if i := strings.LastIndex(value, ";"); i >= 0 {
+			value = value[i+1:]
+		} else {
+			value = ""
+		}
+	}
+	t, err := mail.ParseDate(strings.TrimSpace(value))
+	if err != nil {
+		if *debug {
+			log.Printf("Message ID %s has no usable %s header (%q); using internalDate", redactID(m.Id), name, value)
+		}
+		return m.InternalDate
+	}
+	return t.UnixMilli()
+}
@@ -0,0 +1,85 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"strconv"
+	"strings"
+	"time"
+)
+
+var byDaypart = flag.Bool("by-daypart", false, "report counts per time of day (night, morning, afternoon, evening) in the bucketing timezone instead of per day")
+var daypartBreaks = flag.String("daypart-breaks", "0,6,12,18", "with -by-daypart, the ascending hours at which each time-of-day window starts; the last window runs on past midnight to the first")
+
+// daypartNames label the windows when -daypart-breaks has four of them.
+var daypartNames = []string{"night", "morning", "afternoon", "evening"}
+
+// daypartStarts holds the parsed -daypart-breaks.
+var daypartStarts []int
+
+func parseDaypartBreaks() error {
+	daypartStarts = nil
+	for field := range strings.SplitSeq(*daypartBreaks, ",") {
+		hour, err := strconv.Atoi(strings.TrimSpace(field))
+		if err != nil {
+			return fmt.Errorf("invalid -daypart-breaks %q: %v", *daypartBreaks, err)
+		}
+		if hour < 0 || hour > 23 {
+			return fmt.Errorf("invalid -daypart-breaks %q: hour %d is not between 0 and 23", *daypartBreaks, hour)
+		}
+		if n := len(daypartStarts); n > 0 && hour <= daypartStarts[n-1] {
+			return fmt.Errorf("invalid -daypart-breaks %q: hours must be ascending", *daypartBreaks)
+		}
+		daypartStarts = append(daypartStarts, hour)
+	}
+	if len(daypartStarts) < 2 {
+		return fmt.Errorf("invalid -daypart-breaks %q: want at least two hours", *daypartBreaks)
+	}
+	return nil
+}
+
+// daypart is one row of the -by-daypart summary.
+type daypart struct {
+	name  string
+	hours string
+	count int
+}
+
+// daypartCounts buckets the timestamps into the -daypart-breaks windows.
+func daypartCounts(times []time.Time) []daypart {
+	parts := make([]daypart, len(daypartStarts))
+	for i, start := range daypartStarts {
+		end := daypartStarts[(i+1)%len(daypartStarts)]
+		if end == 0 {
+			end = 24
+		}
+		parts[i].hours = fmt.Sprintf("%02d-%02d", start, end)
+		parts[i].name = fmt.Sprintf("window %d", i+1)
+		if len(daypartStarts) == len(daypartNames) {
+			parts[i].name = daypartNames[i]
+		}
+	}
+	for _, t := range times {
+		// Hours before the first break belong to the window that wraps
+		// past midnight
+		i := len(daypartStarts) - 1
+		for j, start := range daypartStarts {
+			if t.Hour() >= start {
+				i = j
+			}
+		}
+		parts[i].count++
+	}
+	return parts
+}
+
+func printDaypartSummary(times []time.Time) {
+	for _, part := range daypartCounts(times) {
+		percent := 0.0
+		if len(times) > 0 {
+			percent = 100 * float64(part.count) / float64(len(times))
+		}
+		fmt.Printf("%-10s %s %6d %5.1f%%\n", part.name, part.hours, part.count, percent)
+	}
+	fmt.Printf("Total: %d\n", len(times))
+}
@@ -0,0 +1,35 @@
+package main
+
+import (
+	"context"
+	"errors"
+	"flag"
+	"sync/atomic"
+)
+
+var deadline = flag.Duration("deadline", 0, "stop fetching after this long overall (e.g. 5m or 30s) and report what was collected so far, exiting with status 4 (0 = no deadline)")
+
+// exitPartial is the exit status after -deadline cut a run short.
+const exitPartial = 4
+
+// errDeadlineReached is the cancellation cause once -deadline fires.
+var errDeadlineReached = errors.New("-deadline reached")
+
+// deadlineReached records that some listing stopped early because of
+// -deadline, so the results are partial.
+var deadlineReached atomic.Bool
+
+// withDeadline arms -deadline on ctx. The returned cancel func must be
+// called to release the timer.
+func withDeadline(ctx context.Context) (context.Context, context.CancelFunc) {
+	if *deadline <= 0 {
+		return ctx, func() {}
+	}
+	return context.WithTimeoutCause(ctx, *deadline, errDeadlineReached)
+}
+
+// stoppedByDeadline reports whether ctx ended because -deadline fired, as
+// opposed to -timeout or an error.
+func stoppedByDeadline(ctx context.Context) bool {
+	return errors.Is(context.Cause(ctx), errDeadlineReached)
+}
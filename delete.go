@@ -0,0 +1,92 @@
+package main
+
+import (
+	"context"
+	"flag"
+	"fmt"
+	"log"
+	"os"
+	"sync"
+	"sync/atomic"
+
+	"github.com/cenkalti/backoff/v5"
+	"google.golang.org/api/gmail/v1"
+)
+
+var deleteSpam = flag.Bool("delete", false, "after counting, move the counted messages to Trash (requests the gmail.modify scope and asks for confirmation unless -yes)")
+var dryRun = flag.Bool("dry-run", false, "with -delete, list the messages that would be moved to Trash without moving them")
+
+// trashMessages moves the counted messages to Trash, after confirmation.
+// Only messages that contributed to the daily counts are moved.
+func trashMessages(ctx context.Context, srv *gmail.Service, messages []*gmail.Message) error {
+	var ids []string
+	for _, m := range messages {
+		if emailDate, ok := messageDate(m); ok {
+			ids = append(ids, m.Id)
+			if *dryRun {
+				fmt.Printf("Would move message %s (%s) to Trash\n", m.Id, emailDate)
+			}
+		}
+	}
+	if *dryRun {
+		fmt.Printf("Dry run: %d messages would be moved to Trash\n", len(ids))
+		return nil
+	}
+	if len(ids) == 0 {
+		return nil
+	}
+
+	ok, err := confirm(fmt.Sprintf("Move %d messages to Trash?", len(ids)))
+	if err != nil {
+		return fmt.Errorf("unable to read confirmation: %v", err)
+	}
+	if !ok {
+		if !isTerminal(os.Stdin) {
+			return fmt.Errorf("refusing to move %d messages to Trash without -yes", len(ids))
+		}
+		return fmt.Errorf("aborted; no messages were moved to Trash")
+	}
+
+	limiter := newFetchLimiter()
+	var failed atomic.Int64
+	var wg sync.WaitGroup
+	for _, id := range ids {
+		messageId := id
+		wg.Go(func() {
+			if limiter != nil {
+				limiter <- struct{}{}
+				defer func() {
+					<-limiter
+				}()
+			}
+
+			retry := newRetryBackOff()
+			_, err := backoff.Retry(ctx, func() (*gmail.Message, error) {
+				if err := awaitRequestSlot(ctx); err != nil {
+					return nil, backoff.Permanent(err)
+				}
+				stats.quota.Add(quotaMessagesTrash)
+				result, err := srv.Users.Messages.Trash(*mailbox, messageId).Do()
+				if err != nil {
+					if *debug {
+						log.Printf("Error trashing message %s: %v", redactID(messageId), err)
+					}
+					return result, retry.classify(err)
+				}
+				return result, nil
+			}, backoff.WithBackOff(retry))
+			if err != nil {
+				failed.Add(1)
+				recordWarning()
+				log.Printf("Unable to move message %s to Trash: %v", redactID(messageId), err)
+			}
+		})
+	}
+	wg.Wait()
+
+	fmt.Fprintf(status, "Moved %d messages to Trash\n", int64(len(ids))-failed.Load())
+	if failed.Load() > 0 {
+		return fmt.Errorf("%d messages could not be moved to Trash", failed.Load())
+	}
+	return nil
+}
@@ -0,0 +1,97 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"time"
+
+	"golang.org/x/oauth2"
+	"golang.org/x/oauth2/google"
+	"google.golang.org/api/gmail/v1"
+	"google.golang.org/api/option"
+)
+
+// diagnosticCheck is a single named step of the -diagnose checklist.
+type diagnosticCheck struct {
+	name string
+	run  func() error
+}
+
+// runDiagnostics runs each check in order, printing PASS/FAIL with timings.
+// Checks after the first failure are skipped since they depend on it.
+// It returns true if every check passed.
+func runDiagnostics(ctx context.Context) bool {
+	var config *oauth2.Config
+	var ts oauth2.TokenSource
+	var srv *gmail.Service
+
+	checks := []diagnosticCheck{
+		{"Credentials file present and parseable", func() error {
+			b, err := readCredentials()
+			if err != nil {
+				return fmt.Errorf("unable to read client secret: %v", err)
+			}
+			if err := validateCredentials(b); err != nil {
+				return err
+			}
+			// The same scopes as the run, so the token checked is the one
+			// tokenPath picks for it
+			config, err = google.ConfigFromJSON(b, runScopes()...)
+			if err != nil {
+				return fmt.Errorf("unable to parse client secret file to config: %v", err)
+			}
+			applyAuthBaseURL(config)
+			return nil
+		}},
+		{"Token valid or refreshable", func() error {
+			tok, err := loadToken(tokenPath(config))
+			if err != nil {
+				return fmt.Errorf("unable to read cached token: %v", err)
+			}
+			ts = config.TokenSource(ctx, tok)
+			if _, err := ts.Token(); err != nil {
+				return fmt.Errorf("unable to refresh token: %v", err)
+			}
+			return nil
+		}},
+		{"List one spam message", func() error {
+			var err error
+			srv, err = gmail.NewService(ctx, option.WithHTTPClient(oauth2.NewClient(ctx, ts)))
+			if err != nil {
+				return fmt.Errorf("unable to retrieve Gmail client: %v", err)
+			}
+			if _, err := srv.Users.Messages.List(*mailbox).LabelIds("SPAM").MaxResults(1).Do(); err != nil {
+				return fmt.Errorf("unable to list messages: %v", explainMailboxError(err))
+			}
+			return nil
+		}},
+		{"Fetch mailbox profile", func() error {
+			profile, err := srv.Users.GetProfile(*mailbox).Do()
+			if err != nil {
+				return fmt.Errorf("unable to fetch profile: %v", explainMailboxError(err))
+			}
+			fmt.Printf("      Mailbox %s has %d messages\n", profile.EmailAddress, profile.MessagesTotal)
+			return nil
+		}},
+	}
+
+	ok := true
+	for _, check := range checks {
+		if !ok {
+			fmt.Printf("SKIP  %s\n", check.name)
+			continue
+		}
+
+		start := time.Now()
+		err := check.run()
+		elapsed := time.Since(start).Round(time.Millisecond)
+		if err != nil {
+			fmt.Printf("FAIL  %s (%v): %v\n", check.name, elapsed, err)
+			ok = false
+		} else {
+			fmt.Printf("PASS  %s (%v)\n", check.name, elapsed)
+		}
+	}
+
+	return ok
+}
@@ -0,0 +1,69 @@
+package main
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"log"
+	"os"
+	"path/filepath"
+	"strings"
+)
+
+var dumpRaw = flag.String("dump-raw", "", "with -debug, write each raw message list page and fetched message as a JSON file in this directory (one file per message)")
+
+// prepareDumpDir validates -dump-raw and -redact and creates the dump
+// directory.
+func prepareDumpDir() error {
+	if *redact && !*debug {
+		return fmt.Errorf("-redact requires -debug")
+	}
+	if *dumpRaw == "" {
+		return nil
+	}
+	if !*debug {
+		return fmt.Errorf("-dump-raw requires -debug")
+	}
+	// The responses hold no credentials, but do hold message headers, so
+	// keep the directory private
+	if err := os.MkdirAll(*dumpRaw, 0700); err != nil {
+		return fmt.Errorf("unable to create -dump-raw directory %s: %v", *dumpRaw, err)
+	}
+	log.Printf("-dump-raw writes a file per fetched message to %s; large mailboxes produce many files", *dumpRaw)
+	return nil
+}
+
+// dumpFileName makes a Gmail ID or label safe to use in a file name.
+func dumpFileName(s string) string {
+	if s == "" {
+		return "all"
+	}
+	return strings.Map(func(r rune) rune {
+		if r == '/' || r == '\\' || r == os.PathSeparator || r == ':' {
+			return '_'
+		}
+		return r
+	}, s)
+}
+
+// dumpResponse writes v as indented JSON to name in -dump-raw. Failures are
+// logged rather than returned, since the dump is only a debugging aid.
+func dumpResponse(name string, v any) {
+	if *dumpRaw == "" {
+		return
+	}
+	b, err := json.MarshalIndent(v, "", "  ")
+	if err != nil {
+		log.Printf("Unable to encode %s for -dump-raw: %v", name, err)
+		return
+	}
+	if *redact {
+		if b, err = redactJSON(b); err != nil {
+			log.Printf("Unable to redact %s for -dump-raw: %v", name, err)
+			return
+		}
+	}
+	if err := os.WriteFile(filepath.Join(*dumpRaw, name), b, 0600); err != nil {
+		log.Printf("Unable to write %s for -dump-raw: %v", name, err)
+	}
+}
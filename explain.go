@@ -0,0 +1,106 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"strings"
+)
+
+var explain = flag.Bool("explain", false, "describe in plain English what the configured flags would do, then exit (or run, when followed by the count subcommand)")
+
+// explanation summarizes the resolved flags as a sentence, e.g. "Will count
+// messages labeled SPAM received in the past 30 days (since 2024-02-01),
+// bucketed by day in America/New_York, output as CSV to stdout."
+func explanation() string {
+	var b strings.Builder
+	b.WriteString("Will ")
+
+	switch {
+	case *timestamps:
+		b.WriteString("list the timestamp of each message")
+	case *bySender:
+		b.WriteString("count messages per sender")
+	case *byDomain:
+		b.WriteString("count messages per sender domain")
+	case *byDaypart:
+		fmt.Fprintf(&b, "count messages per time of day (windows starting at hours %s)", *daypartBreaks)
+	case *byListID:
+		b.WriteString("count messages per mailing list (List-Id)")
+	case *byReason:
+		b.WriteString("count messages per spam reason")
+	case *domainTiers:
+		b.WriteString("group sender domains into volume tiers for messages")
+	case *senderDiversity:
+		b.WriteString("count messages and distinct senders")
+	case *splitLabels:
+		b.WriteString("count messages per label")
+	default:
+		b.WriteString("count messages")
+	}
+
+	if *useIMAP {
+		fmt.Fprintf(&b, " in the IMAP folder %s", *imapFolder)
+	} else {
+		if *searchAll {
+			b.WriteString(" anywhere in the mailbox")
+		} else {
+			fmt.Fprintf(&b, " labeled %s", strings.Join(labels, " or "))
+		}
+		if filters := queryFilters(); len(filters) > 0 {
+			fmt.Fprintf(&b, " matching %s", strings.Join(filters, " "))
+		}
+	}
+
+	if *allMessages {
+		b.WriteString(" received at any time (no date filter)")
+	} else if *within != "" {
+		fmt.Fprintf(&b, " received in %s (since %s)", windowDescription, cutoffTime.Format("2006-01-02 15:04"))
+	} else if endDate == "" && windowDescription == fmt.Sprintf("the past %v days", *days) {
+		fmt.Fprintf(&b, " received in %s (since %s)", windowDescription, cutoffDate)
+	} else {
+		fmt.Fprintf(&b, " received from %s", windowDescription)
+	}
+
+	if *byDaypart {
+		fmt.Fprintf(&b, ", bucketed by time of day in %s", location)
+	} else if !*bySender && !*byDomain && !*domainTiers && !*byReason && !*byListID {
+		fmt.Fprintf(&b, ", bucketed by day in %s", location)
+	}
+
+	switch {
+	case *compact:
+		b.WriteString(", printed as a single summary line")
+	case *format == "text":
+		b.WriteString(", printed as text")
+	default:
+		fmt.Fprintf(&b, ", output as %s to stdout", strings.ToUpper(*format))
+	}
+
+	var extras []string
+	if *csvOut != "" {
+		extras = append(extras, "written as CSV to "+*csvOut)
+	}
+	if *jsonOut != "" {
+		extras = append(extras, "written as JSON to "+*jsonOut)
+	}
+	if *sheetID != "" {
+		extras = append(extras, "appended to Google Sheet "+*sheetID)
+	}
+	if *webhookURL != "" {
+		extras = append(extras, "posted to "+*webhookURL)
+	}
+	if *useSyslog {
+		extras = append(extras, "sent to syslog")
+	}
+	for _, extra := range extras {
+		b.WriteString(", " + extra)
+	}
+
+	if *deleteSpam && *dryRun {
+		b.WriteString(", then list the messages that would be moved to Trash")
+	} else if *deleteSpam {
+		b.WriteString(", then move the counted messages to Trash")
+	}
+	b.WriteString(".")
+	return b.String()
+}
@@ -6,6 +6,7 @@ import (
 	"encoding/base64"
 	"encoding/json"
 	"errors"
+	"flag"
 	"fmt"
 	"log"
 	"net"
@@ -19,34 +20,196 @@ import (
 	"time"
 
 	"golang.org/x/oauth2"
+	"google.golang.org/api/gmail/v1"
+	"google.golang.org/api/sheets/v4"
 )
 
 const oauthStateTokenBytes = 32
 
-func getClient(ctx context.Context, config *oauth2.Config) *http.Client {
+var noInteractive = flag.Bool("no-interactive", false, "fail instead of starting the browser consent flow when there is no usable cached token (for cron jobs)")
+
+var authTimeout = flag.Int("auth-timeout", 300, "seconds to wait for the browser consent flow to return an authorization code")
+var noTokenPersist = flag.Bool("no-token-persist", false, "keep the OAuth token in memory only, never reading or writing a token file; every run asks for fresh consent")
+
+var credentialsPath = flag.String("credentials", "", "OAuth client secret file (default credentials.json, or GMAIL_CREDENTIALS_JSON / GMAIL_CREDENTIALS_B64 when set)")
+
+var tolerateMissingCreds = flag.Bool("tolerate-missing-creds", false, "when no client secret is found, skip authentication and print an empty report marked as a no-auth placeholder, exiting 0, without touching -on-change state, syslog or -webhook (for smoke-testing pipelines before secrets are provisioned)")
+
+// noAuthPlaceholder is set when -tolerate-missing-creds skipped
+// authentication, so the reports can say they count nothing.
+var noAuthPlaceholder bool
+
+// readCredentials returns the OAuth client secret. An explicit -credentials
+// file wins, then raw JSON in GMAIL_CREDENTIALS_JSON, then base64-encoded
+// JSON in GMAIL_CREDENTIALS_B64 (as Kubernetes secrets are often stored),
+// and finally credentials.json.
+func readCredentials() ([]byte, error) {
+	if *credentialsPath != "" {
+		return os.ReadFile(*credentialsPath)
+	}
+	if raw := os.Getenv("GMAIL_CREDENTIALS_JSON"); raw != "" {
+		return []byte(raw), nil
+	}
+	if encoded := os.Getenv("GMAIL_CREDENTIALS_B64"); encoded != "" {
+		b, err := base64.StdEncoding.DecodeString(strings.TrimSpace(encoded))
+		if err != nil {
+			return nil, fmt.Errorf("failed to base64-decode credentials from GMAIL_CREDENTIALS_B64: %v", err)
+		}
+		return b, nil
+	}
+	return os.ReadFile(credentialsFile)
+}
+
+// oobRedirectURL is the out-of-band copy/paste redirect that Google no longer
+// accepts for installed applications.
+const oobRedirectURL = "urn:ietf:wg:oauth:2.0:oob"
+
+// oobAdvice tells the user how to move off the out-of-band flow.
+const oobAdvice = "Google has deprecated the out-of-band (" + oobRedirectURL + ") OAuth flow. " +
+	"Create a \"Desktop app\" OAuth client in Google Cloud Console and download a new credentials.json, " +
+	"or change redirect_uris in credentials.json to [\"http://localhost\"] to use the loopback redirect"
+
+// validateCredentials checks that the client secret has every field the
+// installed-app flow needs, naming the first one missing. Otherwise
+// google.ConfigFromJSON accepts a partial secret and the flow fails later
+// with a much less helpful error.
+func validateCredentials(b []byte) error {
+	var secret map[string]map[string]any
+	if err := json.Unmarshal(b, &secret); err != nil {
+		return fmt.Errorf("client secret is not valid JSON: %v", err)
+	}
+
+	kind := "installed"
+	client, ok := secret[kind]
+	if !ok {
+		kind = "web"
+		if client, ok = secret[kind]; !ok {
+			return fmt.Errorf("client secret has no \"installed\" section; download a Desktop app OAuth client from Google Cloud Console")
+		}
+	}
+	for _, field := range []string{"client_id", "client_secret", "auth_uri", "token_uri", "redirect_uris"} {
+		value, ok := client[field]
+		if !ok {
+			return fmt.Errorf("client secret is missing %s.%s", kind, field)
+		}
+		if uris, isList := value.([]any); isList && len(uris) == 0 {
+			return fmt.Errorf("client secret has an empty %s.%s", kind, field)
+		}
+		if str, isString := value.(string); isString && strings.TrimSpace(str) == "" {
+			return fmt.Errorf("client secret has an empty %s.%s", kind, field)
+		}
+	}
+	return nil
+}
+
+// applyAuthBaseURL points the OAuth consent and token endpoints at
+// GMAIL_AUTH_BASE_URL (its /auth and /token paths) when set. It is a testing
+// seam: a local fake server can hand out canned tokens, or refuse them, to
+// exercise the auth path without Google. Gmail requests still go to Google.
+func applyAuthBaseURL(config *oauth2.Config) {
+	base := strings.TrimSuffix(os.Getenv("GMAIL_AUTH_BASE_URL"), "/")
+	if base == "" {
+		return
+	}
+	config.Endpoint.AuthURL = base + "/auth"
+	config.Endpoint.TokenURL = base + "/token"
+	log.Printf("Using OAuth endpoints at %s (GMAIL_AUTH_BASE_URL)", base)
+}
+
+// usesOOBRedirect reports whether config is set up for the deprecated out-of-band flow.
+func usesOOBRedirect(config *oauth2.Config) bool {
+	redirectURL := strings.TrimSpace(config.RedirectURL)
+	return redirectURL == oobRedirectURL || strings.HasPrefix(redirectURL, oobRedirectURL+":")
+}
+
+// runScopes returns the OAuth scopes this run needs.
+// If modifying these scopes, delete your previously saved token.json.
+// Extra scopes are only requested by the flags that need them, and their
+// tokens are cached separately (see tokenPath).
+func runScopes() []string {
+	scopes := []string{gmail.GmailReadonlyScope}
+	if *deleteSpam && !*dryRun {
+		scopes[0] = gmail.GmailModifyScope
+	}
+	if *sheetID != "" {
+		scopes = append(scopes, sheets.SpreadsheetsScope)
+	}
+	return scopes
+}
+
+// tokenFile is where the OAuth token is cached between runs.
+const tokenFile = "token.json"
+
+// tokenPath returns the token cache matching the scopes config requests.
+// Tokens granted extra scopes (gmail.modify for -delete, spreadsheets for
+// -sheet-id) are cached separately, e.g. token-modify.json, so the everyday
+// read-only token is never widened.
+func tokenPath(config *oauth2.Config) string {
+	var extras []string
+	for _, scope := range config.Scopes {
+		switch scope {
+		case gmail.GmailModifyScope:
+			extras = append(extras, "modify")
+		case sheets.SpreadsheetsScope:
+			extras = append(extras, "sheets")
+		}
+	}
+	if len(extras) == 0 {
+		return tokenFile
+	}
+	return "token-" + strings.Join(extras, "-") + ".json"
+}
+
+func getClient(ctx context.Context, config *oauth2.Config, tokFile string, opts ...oauth2.AuthCodeOption) *http.Client {
 	// Retrieve a token, saves the token, then returns the generated client.
 	// Changed to return a TokenSource instead of an http.Client
-	ts := getTokenSource(ctx, config)
+	ts := getTokenSource(ctx, config, tokFile, opts...)
+
+	// Fetch the first token eagerly so an offline machine gets a clear error
+	// instead of a transport error buried in the fetch retries.
+	if _, err := ts.Token(); err != nil {
+		if isNetworkUnreachable(err) {
+			fatalf("%s (%v)", networkUnreachableMessage, err)
+		}
+		if *noInteractive {
+			fatalf("Unable to use cached token: %v (interactive auth required but -no-interactive set)", err)
+		}
+	}
 	return oauth2.NewClient(ctx, ts)
 }
 
 // Retrieve a token, saves the token, then returns the generated client.
 // Changed to return a TokenSource instead of an http.Client
-func getTokenSource(ctx context.Context, config *oauth2.Config) oauth2.TokenSource {
-	tokFile := "token.json"
-	tok, err := tokenFromFile(tokFile)
+func getTokenSource(ctx context.Context, config *oauth2.Config, tokFile string, opts ...oauth2.AuthCodeOption) oauth2.TokenSource {
+	if *noTokenPersist {
+		// Ephemeral mode: consent every run and leave nothing on disk
+		return config.TokenSource(ctx, getTokenFromWeb(ctx, config, opts...))
+	}
+
+	tok, err := loadToken(tokFile)
 	if err != nil {
-		tok = getTokenFromWeb(ctx, config)
-		saveToken(tokFile, tok)
+		if *noInteractive {
+			if errors.Is(err, os.ErrNotExist) {
+				// The usual first cron run: nothing has ever been authorized
+				fatalf("No cached token at %s: run once without -no-interactive to authorize in a browser and save the token, then rerun", tokFile)
+			}
+			fatalf("Unable to read cached token %s: %v (interactive auth required but -no-interactive set)", tokFile, err)
+		}
+		tok = getTokenFromWeb(ctx, config, opts...)
+		storeToken(tokFile, tok)
 	}
 
 	// Create a new TokenSource that can refresh the token
-	ts := config.TokenSource(context.Background(), tok)
+	ts := config.TokenSource(ctx, tok)
 	return ts
 }
 
-// Request a token from the web, then returns the retrieved token.
-func getTokenFromWeb(ctx context.Context, config *oauth2.Config) *oauth2.Token {
+// Request a token from the web, then returns the retrieved token. opts are
+// added to the consent URL, e.g. a login_hint for -accounts.
+func getTokenFromWeb(ctx context.Context, config *oauth2.Config, opts ...oauth2.AuthCodeOption) *oauth2.Token {
+	if usesOOBRedirect(config) {
+		fatalf("Unable to authorize: %s.", oobAdvice)
+	}
 	if err := ensureRedirectURLHasLocalPort(config); err != nil {
 		log.Printf("OAuth callback server unavailable: %v", err)
 		log.Printf("Continuing with manual authorization code entry.")
@@ -55,10 +218,10 @@ func getTokenFromWeb(ctx context.Context, config *oauth2.Config) *oauth2.Token {
 
 	state, err := newOAuthStateToken()
 	if err != nil {
-		log.Fatalf("Unable to generate OAuth state token: %v", err)
+		fatalf("Unable to generate OAuth state token: %v", err)
 	}
 
-	authURL := config.AuthCodeURL(state, oauth2.AccessTypeOffline)
+	authURL := config.AuthCodeURL(state, append([]oauth2.AuthCodeOption{oauth2.AccessTypeOffline}, opts...)...)
 	fmt.Printf("Go to the following link in your browser then type the "+
 		"authorization code: \n%v\n", authURL)
 
@@ -85,7 +248,7 @@ func getTokenFromWeb(ctx context.Context, config *oauth2.Config) *oauth2.Token {
 
 		var authCode string
 		if _, err := fmt.Scan(&authCode); err != nil {
-			log.Fatalf("Unable to scan authorization code: %v", err)
+			fatalf("Unable to scan authorization code: %v", err)
 		}
 		authCodeChan <- authCode
 	}()
@@ -103,14 +266,17 @@ func getTokenFromWeb(ctx context.Context, config *oauth2.Config) *oauth2.Token {
 
 	var authCode string
 	select {
-	case <-time.After(60 * time.Second):
-		log.Fatal("Timed out waiting for authorization code.")
+	case <-time.After(time.Duration(*authTimeout) * time.Second):
+		fatalf("Unable to authorize: consent timed out after %ds (see -auth-timeout)", *authTimeout)
 	case authCode = <-authCodeChan:
 	}
 
 	tok, exchangeErr := config.Exchange(ctx, authCode)
 	if exchangeErr != nil {
-		log.Fatalf("Unable to retrieve token from web: %v", exchangeErr)
+		if isNetworkUnreachable(exchangeErr) {
+			fatalf("%s (%v)", networkUnreachableMessage, exchangeErr)
+		}
+		fatalf("Unable to retrieve token from web: %v", exchangeErr)
 	}
 	return tok
 }
@@ -253,7 +419,7 @@ func saveToken(path string, token *oauth2.Token) {
 	fmt.Printf("Saving credential file to: %s\n", path)
 	f, err := os.OpenFile(path, os.O_RDWR|os.O_CREATE|os.O_TRUNC, 0600)
 	if err != nil {
-		log.Fatalf("Unable to cache oauth token: %v", err)
+		fatalf("Unable to cache oauth token: %v", err)
 	}
 	defer f.Close()
 	json.NewEncoder(f).Encode(token)
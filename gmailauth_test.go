@@ -0,0 +1,62 @@
+package main
+
+import (
+	"strings"
+	"testing"
+
+	"golang.org/x/oauth2"
+)
+
+func TestValidateCredentials(t *testing.T) {
+	tests := []struct {
+		name    string
+		secret  string
+		wantErr string
+	}{
+		{"complete installed client", `{"installed": {"client_id": "id", "client_secret": "secret", "auth_uri": "https://a", "token_uri": "https://t", "redirect_uris": ["http://localhost"]}}`, ""},
+		{"complete web client", `{"web": {"client_id": "id", "client_secret": "secret", "auth_uri": "https://a", "token_uri": "https://t", "redirect_uris": ["http://localhost"]}}`, ""},
+		{"missing redirect_uris", `{"installed": {"client_id": "id", "client_secret": "secret", "auth_uri": "https://a", "token_uri": "https://t"}}`, "missing installed.redirect_uris"},
+		{"empty redirect_uris", `{"installed": {"client_id": "id", "client_secret": "secret", "auth_uri": "https://a", "token_uri": "https://t", "redirect_uris": []}}`, "empty installed.redirect_uris"},
+		{"empty client_id", `{"installed": {"client_id": " ", "client_secret": "secret", "auth_uri": "https://a", "token_uri": "https://t", "redirect_uris": ["http://localhost"]}}`, "empty installed.client_id"},
+		{"no client section", `{"service_account": {}}`, "no \"installed\" section"},
+		{"not JSON", `client_id=id`, "not valid JSON"},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			err := validateCredentials([]byte(tt.secret))
+			if tt.wantErr == "" {
+				if err != nil {
+					t.Errorf("validateCredentials: %v", err)
+				}
+				return
+			}
+			if err == nil || !strings.Contains(err.Error(), tt.wantErr) {
+				t.Errorf("validateCredentials = %v, want an error containing %q", err, tt.wantErr)
+			}
+		})
+	}
+}
+
+func TestRunScopesTokenPath(t *testing.T) {
+	oldDelete, oldDryRun, oldSheet := *deleteSpam, *dryRun, *sheetID
+	t.Cleanup(func() { *deleteSpam, *dryRun, *sheetID = oldDelete, oldDryRun, oldSheet })
+
+	tests := []struct {
+		name               string
+		deleteSpam, dryRun bool
+		sheetID, wantToken string
+	}{
+		{"read only", false, false, "", "token.json"},
+		{"delete", true, false, "", "token-modify.json"},
+		{"delete dry run", true, true, "", "token.json"},
+		{"sheets", false, false, "sheet", "token-sheets.json"},
+		{"delete and sheets", true, false, "sheet", "token-modify-sheets.json"},
+	}
+	for _, tt := range tests {
+		*deleteSpam, *dryRun, *sheetID = tt.deleteSpam, tt.dryRun, tt.sheetID
+		config := &oauth2.Config{Scopes: runScopes()}
+		if got := tokenPath(config); got != tt.wantToken {
+			t.Errorf("%s: tokenPath = %q, want %q", tt.name, got, tt.wantToken)
+		}
+	}
+}
@@ -0,0 +1,41 @@
+package main
+
+import (
+	"flag"
+	"log"
+	"time"
+)
+
+var heartbeat = flag.Int("heartbeat", 0, "log a \"still working\" line with fetch progress to stderr every N seconds (0 = off)")
+
+// startHeartbeat logs progress every -heartbeat seconds until the returned
+// stop func is called. It is a no-op when -heartbeat is off.
+func startHeartbeat() (stop func()) {
+	if *heartbeat <= 0 {
+		return func() {}
+	}
+
+	done := make(chan struct{})
+	finished := make(chan struct{})
+	go func() {
+		defer close(finished)
+		ticker := time.NewTicker(time.Duration(*heartbeat) * time.Second)
+		defer ticker.Stop()
+		for {
+			select {
+			case <-ticker.C:
+				if estimate := stats.estimated.Load(); estimate > 0 {
+					log.Printf("Still working... fetched %d of ~%d messages", stats.fetched.Load(), estimate)
+				} else {
+					log.Printf("Still working... fetched %d messages", stats.fetched.Load())
+				}
+			case <-done:
+				return
+			}
+		}
+	}()
+	return func() {
+		close(done)
+		<-finished
+	}
+}
@@ -0,0 +1,68 @@
+package main
+
+import (
+	"fmt"
+	"html"
+	"io"
+	"log"
+	"sort"
+	"time"
+)
+
+// peakStyle highlights the busiest day in -format html output.
+const peakStyle = ` style="font-weight:bold;background:#fdd"`
+
+// writeHTMLSummary writes the daily counts as a self-contained <table>
+// fragment, with the peak day highlighted and a total footer row.
+func writeHTMLSummary(w io.Writer, spamCounts map[string]int) error {
+	var dates []string
+	for date := range spamCounts {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	total, peak := 0, 0
+	for _, count := range spamCounts {
+		total += count
+		peak = max(peak, count)
+	}
+
+	fmt.Fprintln(w, "<table>")
+	fmt.Fprintln(w, "<thead><tr><th>Day</th><th>Date</th><th>Count</th></tr></thead>")
+	fmt.Fprintln(w, "<tbody>")
+	for _, date := range dates {
+		dateValue, err := time.Parse("2006-01-02", date)
+		if err != nil {
+			log.Printf("Error parsing date: %v", err)
+			continue
+		}
+		count := spamCounts[date]
+		style := ""
+		if count == peak && peak > 0 {
+			style = peakStyle
+		}
+		fmt.Fprintf(w, "<tr%s><td>%s</td><td>%s</td><td>%d</td></tr>\n",
+			style, dateValue.Format("Mon"), html.EscapeString(displayDate(dateValue)), count)
+	}
+	fmt.Fprintln(w, "</tbody>")
+	fmt.Fprintf(w, "<tfoot><tr><th colspan=\"2\">Total</th><th>%d</th></tr></tfoot>\n", total)
+	_, err := fmt.Fprintln(w, "</table>")
+	return err
+}
+
+// writeSenderHTMLSummary writes the sender or domain counts as a <table>.
+// Senders come straight from message headers, so they are always escaped.
+func writeSenderHTMLSummary(w io.Writer, senderCounts map[string]int) error {
+	fmt.Fprintln(w, "<table>")
+	fmt.Fprintf(w, "<thead><tr><th>%s</th><th>Count</th><th>Percent</th></tr></thead>\n", html.EscapeString(senderColumn()))
+	fmt.Fprintln(w, "<tbody>")
+	total := countsTotal(senderCounts)
+	for _, sender := range sortedSenders(senderCounts) {
+		count := senderCounts[sender]
+		fmt.Fprintf(w, "<tr><td>%s</td><td>%d</td><td>%.1f%%</td></tr>\n", html.EscapeString(sender), count, percentOf(count, total))
+	}
+	fmt.Fprintln(w, "</tbody>")
+	fmt.Fprintf(w, "<tfoot><tr><th>Total</th><th>%d</th><th></th></tr></tfoot>\n", total)
+	_, err := fmt.Fprintln(w, "</table>")
+	return err
+}
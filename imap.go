@@ -0,0 +1,243 @@
+package main
+
+import (
+	"bufio"
+	"context"
+	"crypto/tls"
+	"flag"
+	"fmt"
+	"log"
+	"net"
+	"os"
+	"regexp"
+	"strings"
+	"time"
+)
+
+var useIMAP = flag.Bool("imap", false, "count spam over IMAP with an app password (from GMAIL_IMAP_USER and GMAIL_IMAP_PASSWORD) instead of the Gmail API")
+var imapFolder = flag.String("imap-folder", "[Gmail]/Spam", "IMAP folder to count with -imap (\"[Google Mail]/Spam\" in some locales)")
+
+const imapAddress = "imap.gmail.com:993"
+
+// imapFetchBatch limits how many sequence numbers go into a single FETCH.
+const imapFetchBatch = 500
+
+// imapInternalDate matches the INTERNALDATE item of a FETCH response.
+var imapInternalDate = regexp.MustCompile(`INTERNALDATE "([^"]+)"`)
+
+// validateIMAP rejects flags that depend on the Gmail API when -imap is set.
+func validateIMAP() error {
+	if !*useIMAP {
+		return nil
+	}
+	unsupported := []struct {
+		name string
+		on   bool
+	}{
+		{"-split-labels", *splitLabels},
+		{"-sender-diversity", *senderDiversity},
+		{"-by-sender", *bySender},
+		{"-by-domain", *byDomain},
+		{"-domain-tiers", *domainTiers},
+		{"-by-reason", *byReason},
+		{"-by-list-id", *byListID},
+		{"-by-daypart", *byDaypart},
+		{"-timestamps", *timestamps},
+		{"-label", flagWasSet("label")},
+		{"-starred", *starred},
+		{"-important", *important},
+		{"-has-attachment", *hasAttachment},
+		{"-min-size", *minSize > 0},
+		{"-allowlist", *allowlistPath != ""},
+		{"-checkpoint", *checkpointPath != ""},
+		{"-delete", *deleteSpam},
+		{"-sheet-id", *sheetID != ""},
+		{"-list-labels", *listLabels},
+		{"-date-source", *dateSource != "internal"},
+		{"-query", *customQuery != ""},
+		{"-search-all", *searchAll},
+		{"-subject-regex", *subjectRegex != ""},
+	}
+	for _, u := range unsupported {
+		if u.on {
+			return fmt.Errorf("-imap cannot be combined with %s", u.name)
+		}
+	}
+	return nil
+}
+
+// imapConn is a minimal IMAP4rev1 client: just enough to log in, open a
+// folder read-only and read INTERNALDATEs.
+type imapConn struct {
+	conn net.Conn
+	r    *bufio.Reader
+	tag  int
+}
+
+func dialIMAP(ctx context.Context) (*imapConn, error) {
+	dialer := &tls.Dialer{Config: &tls.Config{ServerName: "imap.gmail.com"}}
+	conn, err := dialer.DialContext(ctx, "tcp", imapAddress)
+	if err != nil {
+		return nil, fmt.Errorf("unable to connect to %s: %v", imapAddress, err)
+	}
+	if deadline, ok := ctx.Deadline(); ok {
+		conn.SetDeadline(deadline)
+	}
+
+	c := &imapConn{conn: conn, r: bufio.NewReader(conn)}
+	greeting, err := c.readLine()
+	if err != nil {
+		conn.Close()
+		return nil, fmt.Errorf("unable to read IMAP greeting: %v", err)
+	}
+	if !strings.HasPrefix(greeting, "* OK") {
+		conn.Close()
+		return nil, fmt.Errorf("unexpected IMAP greeting: %s", greeting)
+	}
+	return c, nil
+}
+
+func (c *imapConn) readLine() (string, error) {
+	line, err := c.r.ReadString('\n')
+	if err != nil {
+		return "", err
+	}
+	return strings.TrimRight(line, "\r\n"), nil
+}
+
+// command sends one tagged command and returns its untagged response lines.
+func (c *imapConn) command(format string, args ...any) ([]string, error) {
+	c.tag++
+	tag := fmt.Sprintf("a%d", c.tag)
+	cmd := fmt.Sprintf(format, args...)
+	if _, err := fmt.Fprintf(c.conn, "%s %s\r\n", tag, cmd); err != nil {
+		return nil, err
+	}
+
+	var untagged []string
+	for {
+		line, err := c.readLine()
+		if err != nil {
+			return nil, err
+		}
+		if !strings.HasPrefix(line, tag+" ") {
+			untagged = append(untagged, line)
+			continue
+		}
+		result := strings.TrimPrefix(line, tag+" ")
+		if !strings.HasPrefix(result, "OK") {
+			// Don't echo the command, which may hold the password
+			verb, _, _ := strings.Cut(cmd, " ")
+			return nil, fmt.Errorf("IMAP %s failed: %s", verb, result)
+		}
+		return untagged, nil
+	}
+}
+
+func (c *imapConn) close() {
+	c.command("LOGOUT")
+	c.conn.Close()
+}
+
+// imapQuote returns s as an IMAP quoted string.
+func imapQuote(s string) string {
+	s = strings.ReplaceAll(s, `\`, `\\`)
+	return `"` + strings.ReplaceAll(s, `"`, `\"`) + `"`
+}
+
+// imapSearchDate converts a YYYY-MM-DD date to the IMAP date format.
+func imapSearchDate(date string) (string, error) {
+	t, err := time.Parse("2006-01-02", date)
+	if err != nil {
+		return "", err
+	}
+	return t.Format("2-Jan-2006"), nil
+}
+
+// getIMAPSpamCounts counts the messages in -imap-folder by INTERNALDATE over
+// the same window as the Gmail API query.
+func getIMAPSpamCounts(ctx context.Context) (map[string]int, error) {
+	user, password := os.Getenv("GMAIL_IMAP_USER"), os.Getenv("GMAIL_IMAP_PASSWORD")
+	if user == "" || password == "" {
+		return nil, fmt.Errorf("-imap needs GMAIL_IMAP_USER and GMAIL_IMAP_PASSWORD (an app password, not your account password)")
+	}
+
+	ctx, cancel := context.WithTimeout(ctx, time.Duration(*timeout)*time.Second)
+	defer cancel()
+
+	c, err := dialIMAP(ctx)
+	if err != nil {
+		return nil, err
+	}
+	defer c.close()
+
+	if _, err := c.command("LOGIN %s %s", imapQuote(user), imapQuote(password)); err != nil {
+		return nil, err
+	}
+	// EXAMINE opens the folder read-only, so counting never marks anything seen
+	if _, err := c.command("EXAMINE %s", imapQuote(*imapFolder)); err != nil {
+		return nil, err
+	}
+
+	// Like buildQuery, search a day past each end of the window and then
+	// filter by INTERNALDATE in the report timezone
+	criteria := "ALL"
+	if cutoffDate != "" {
+		since, err := imapSearchDate(shiftDate(cutoffDate, -1))
+		if err != nil {
+			return nil, err
+		}
+		criteria = "SINCE " + since
+	}
+	if endDate != "" {
+		before, err := imapSearchDate(shiftDate(endDate, 1))
+		if err != nil {
+			return nil, err
+		}
+		criteria += " BEFORE " + before
+	}
+	if *debug {
+		log.Printf("IMAP search in %s: %s", *imapFolder, criteria)
+	}
+
+	lines, err := c.command("SEARCH %s", criteria)
+	if err != nil {
+		return nil, err
+	}
+	var ids []string
+	for _, line := range lines {
+		if rest, ok := strings.CutPrefix(line, "* SEARCH"); ok {
+			ids = append(ids, strings.Fields(rest)...)
+		}
+	}
+	if len(ids) == 0 {
+		fmt.Fprintln(status, "No spam messages found.")
+		return map[string]int{}, nil
+	}
+
+	spamCounts := make(map[string]int)
+	for start := 0; start < len(ids); start += imapFetchBatch {
+		end := min(start+imapFetchBatch, len(ids))
+		lines, err := c.command("FETCH %s (INTERNALDATE)", strings.Join(ids[start:end], ","))
+		if err != nil {
+			return nil, err
+		}
+		for _, line := range lines {
+			match := imapInternalDate.FindStringSubmatch(line)
+			if match == nil {
+				continue
+			}
+			t, err := time.Parse("_2-Jan-2006 15:04:05 -0700", match[1])
+			if err != nil {
+				return nil, fmt.Errorf("unable to parse INTERNALDATE %q: %v", match[1], err)
+			}
+			if date := t.In(location).Format("2006-01-02"); inWindow(date) && !beforeCutoffTime(t.UnixMilli()) {
+				spamCounts[date]++
+			}
+		}
+		if *debug {
+			log.Printf("IMAP fetched %d of %d dates", end, len(ids))
+		}
+	}
+	return spamCounts, nil
+}
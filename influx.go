@@ -0,0 +1,42 @@
+package main
+
+import (
+	"fmt"
+	"io"
+	"log"
+	"sort"
+	"strings"
+	"time"
+)
+
+// influxTagEscaper escapes InfluxDB line protocol tag keys and values.
+var influxTagEscaper = strings.NewReplacer(`,`, `\,`, `=`, `\=`, ` `, `\ `)
+
+// writeInfluxSummary writes one InfluxDB line protocol record per day, e.g.
+// "spam,label=SPAM,account=me count=42i 1710374400000000000", timestamped at
+// the day's midnight in the bucketing timezone.
+func writeInfluxSummary(w io.Writer, spamCounts map[string]int) error {
+	var dates []string
+	for date := range spamCounts {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	label := strings.Join(labels, ",")
+	if *searchAll {
+		// Tag values can't be empty
+		label = "all"
+	}
+	tags := fmt.Sprintf("label=%s,account=%s", influxTagEscaper.Replace(label), influxTagEscaper.Replace(*mailbox))
+	for _, date := range dates {
+		midnight, err := time.ParseInLocation("2006-01-02", date, location)
+		if err != nil {
+			log.Printf("Error parsing date: %v", err)
+			continue
+		}
+		if _, err := fmt.Fprintf(w, "spam,%s count=%di %d\n", tags, spamCounts[date], midnight.UnixNano()); err != nil {
+			return err
+		}
+	}
+	return nil
+}
@@ -0,0 +1,139 @@
+package main
+
+import (
+	"bytes"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"flag"
+	"fmt"
+	"log"
+	"os"
+	"os/exec"
+	"runtime"
+	"strings"
+
+	"golang.org/x/oauth2"
+)
+
+var useKeyring = flag.Bool("keyring", false, "keep the OAuth token in the OS secret store (macOS Keychain, or the Secret Service via secret-tool on Linux) instead of a plaintext token file; falls back to the file with a warning when no secret store is available or it holds no token")
+
+// keyringService names the secret store entries; the account is the token
+// file name, so tokens for different scopes stay separate.
+const keyringService = "gocheckspam"
+
+// errKeyringUnavailable means there is no secret store to use on this
+// machine, as opposed to one that has no token saved.
+var errKeyringUnavailable = errors.New("no OS secret store available")
+
+// keyringGet returns the secret saved for account. It returns an error
+// wrapping os.ErrNotExist when the store has no entry.
+func keyringGet(account string) ([]byte, error) {
+	var cmd *exec.Cmd
+	switch runtime.GOOS {
+	case "darwin":
+		cmd = exec.Command("security", "find-generic-password", "-s", keyringService, "-a", account, "-w")
+	case "linux":
+		cmd = exec.Command("secret-tool", "lookup", "service", keyringService, "account", account)
+	default:
+		return nil, errKeyringUnavailable
+	}
+
+	out, err := cmd.Output()
+	var exitErr *exec.ExitError
+	if errors.As(err, &exitErr) {
+		if keyringMiss(exitErr) {
+			return nil, fmt.Errorf("no %s entry for %s in the secret store: %w", keyringService, account, os.ErrNotExist)
+		}
+		// e.g. secret-tool with no Secret Service running on the session bus
+		return nil, fmt.Errorf("%w: %v: %s", errKeyringUnavailable, err, bytes.TrimSpace(exitErr.Stderr))
+	}
+	if err != nil {
+		return nil, fmt.Errorf("%w: %v", errKeyringUnavailable, err)
+	}
+	return bytes.TrimSpace(out), nil
+}
+
+// keyringMiss reports whether a failed lookup means the store works but holds
+// no entry: security exits 44 (errSecItemNotFound), and secret-tool exits
+// non-zero without printing anything.
+func keyringMiss(exitErr *exec.ExitError) bool {
+	if runtime.GOOS == "darwin" {
+		return exitErr.ExitCode() == 44
+	}
+	return len(bytes.TrimSpace(exitErr.Stderr)) == 0
+}
+
+// keyringSet saves secret for account, replacing any previous entry. The
+// secret goes over stdin so it never appears in the process list.
+func keyringSet(account string, secret []byte) error {
+	var cmd *exec.Cmd
+	switch runtime.GOOS {
+	case "darwin":
+		// security -i reads its commands from stdin; -X takes the secret as hex
+		cmd = exec.Command("security", "-i")
+		cmd.Stdin = strings.NewReader(fmt.Sprintf("add-generic-password -U -s %s -a %s -X %s\n", keyringService, account, hex.EncodeToString(secret)))
+	case "linux":
+		cmd = exec.Command("secret-tool", "store", "--label", keyringService+" "+account, "service", keyringService, "account", account)
+		cmd.Stdin = bytes.NewReader(secret)
+	default:
+		return errKeyringUnavailable
+	}
+
+	if out, err := cmd.CombinedOutput(); err != nil {
+		var exitErr *exec.ExitError
+		if errors.As(err, &exitErr) {
+			return fmt.Errorf("%v: %s", err, bytes.TrimSpace(out))
+		}
+		return fmt.Errorf("%w: %v", errKeyringUnavailable, err)
+	}
+	return nil
+}
+
+// loadToken reads the cached token for tokFile, from the secret store under
+// -keyring and otherwise from the file itself. Under -keyring the file is
+// still read when the store is unavailable or has no entry.
+func loadToken(tokFile string) (*oauth2.Token, error) {
+	if !*useKeyring {
+		return tokenFromFile(tokFile)
+	}
+	b, err := keyringGet(tokFile)
+	if errors.Is(err, errKeyringUnavailable) {
+		log.Printf("WARNING: -keyring: %v; reading the token from %s instead", err, tokFile)
+		return tokenFromFile(tokFile)
+	}
+	if errors.Is(err, os.ErrNotExist) {
+		// A token saved before -keyring, or by storeToken's fallback when the
+		// store refused it, is still in the file
+		if tok, fileErr := tokenFromFile(tokFile); fileErr == nil {
+			log.Printf("WARNING: -keyring: no token in the secret store; using the one in %s", tokFile)
+			return tok, nil
+		}
+		return nil, err
+	}
+	if err != nil {
+		return nil, err
+	}
+	tok := &oauth2.Token{}
+	if err := json.Unmarshal(b, tok); err != nil {
+		return nil, fmt.Errorf("unable to parse the token in the secret store: %v", err)
+	}
+	return tok, nil
+}
+
+// storeToken caches the token for tokFile, in the secret store under -keyring
+// when it accepts it and otherwise in the file.
+func storeToken(tokFile string, tok *oauth2.Token) {
+	if *useKeyring {
+		b, err := json.Marshal(tok)
+		if err == nil {
+			err = keyringSet(tokFile, b)
+		}
+		if err == nil {
+			fmt.Printf("Saved credential to the %s secret store entry %s\n", keyringService, tokFile)
+			return
+		}
+		log.Printf("WARNING: -keyring: unable to save the token: %v; saving it to %s instead", err, tokFile)
+	}
+	saveToken(tokFile, tok)
+}
@@ -0,0 +1,78 @@
+package main
+
+import (
+	"errors"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"runtime"
+	"testing"
+
+	"golang.org/x/oauth2"
+)
+
+// fakeSecretTool puts a secret-tool on PATH that runs script.
+func fakeSecretTool(t *testing.T, script string) {
+	t.Helper()
+	if runtime.GOOS != "linux" {
+		t.Skip("secret-tool is only used on Linux")
+	}
+	dir := t.TempDir()
+	if err := os.WriteFile(filepath.Join(dir, "secret-tool"), []byte("#!/bin/sh\n"+script+"\n"), 0755); err != nil {
+		t.Fatal(err)
+	}
+	t.Setenv("PATH", dir)
+}
+
+func TestKeyringMiss(t *testing.T) {
+	if runtime.GOOS != "linux" {
+		t.Skip("exit statuses checked are secret-tool's")
+	}
+	if !keyringMiss(&exec.ExitError{}) {
+		t.Error("a silent non-zero exit was not treated as a missing entry")
+	}
+	if keyringMiss(&exec.ExitError{Stderr: []byte("Cannot autolaunch D-Bus without X11 $DISPLAY")}) {
+		t.Error("a secret-tool error was treated as a missing entry")
+	}
+}
+
+func TestLoadTokenFallsBackToFile(t *testing.T) {
+	tests := []struct {
+		name   string
+		script string
+	}{
+		{"no entry in the store", "exit 1"},
+		{"no secret service", "echo 'Cannot autolaunch D-Bus without X11 $DISPLAY' >&2; exit 1"},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			fakeSecretTool(t, tt.script)
+			oldKeyring := *useKeyring
+			t.Cleanup(func() { *useKeyring = oldKeyring })
+			*useKeyring = true
+
+			tokFile := filepath.Join(t.TempDir(), "token.json")
+			saveToken(tokFile, &oauth2.Token{AccessToken: "cached", RefreshToken: "refresh"})
+
+			tok, err := loadToken(tokFile)
+			if err != nil {
+				t.Fatalf("loadToken: %v", err)
+			}
+			if tok.RefreshToken != "refresh" {
+				t.Errorf("loadToken returned %+v, want the token from %s", tok, tokFile)
+			}
+		})
+	}
+}
+
+func TestLoadTokenMissingEverywhere(t *testing.T) {
+	fakeSecretTool(t, "exit 1")
+	oldKeyring := *useKeyring
+	t.Cleanup(func() { *useKeyring = oldKeyring })
+	*useKeyring = true
+
+	_, err := loadToken(filepath.Join(t.TempDir(), "token.json"))
+	if !errors.Is(err, os.ErrNotExist) {
+		t.Errorf("loadToken = %v, want an os.ErrNotExist error so the consent flow runs", err)
+	}
+}
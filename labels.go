@@ -0,0 +1,180 @@
+package main
+
+import (
+	"context"
+	"flag"
+	"fmt"
+	"log"
+	"os"
+	"sort"
+	"strings"
+	"text/tabwriter"
+
+	"github.com/cenkalti/backoff/v5"
+	"google.golang.org/api/gmail/v1"
+)
+
+var listLabels = flag.Bool("list-labels", false, "list the mailbox's labels with their message counts and exit")
+
+// printLabels lists every label with its total and unread message counts,
+// system labels first. labels.list omits the counts, so each label is
+// fetched individually.
+func printLabels(ctx context.Context, srv *gmail.Service) error {
+	list, err := mailboxLabels(ctx, srv)
+	if err != nil {
+		return err
+	}
+
+	var details []*gmail.Label
+	for _, label := range list {
+		labelId := label.Id
+		retry := newRetryBackOff()
+		detail, err := backoff.Retry(ctx, func() (*gmail.Label, error) {
+			stats.quota.Add(quotaLabelsGet)
+			l, err := srv.Users.Labels.Get(*mailbox, labelId).Do()
+			if err != nil {
+				if *debug {
+					log.Printf("Error fetching label %s: %v", labelId, err)
+				}
+				return l, retry.classify(err)
+			}
+			return l, nil
+		}, backoff.WithBackOff(retry))
+		if err != nil {
+			return fmt.Errorf("unable to fetch label %s: %v", labelId, err)
+		}
+		details = append(details, detail)
+	}
+
+	sort.Slice(details, func(i, j int) bool {
+		if details[i].Type != details[j].Type {
+			return details[i].Type == "system"
+		}
+		return details[i].Name < details[j].Name
+	})
+
+	tw := tabwriter.NewWriter(os.Stdout, 0, 0, 2, ' ', 0)
+	fmt.Fprintln(tw, "ID\tName\tTotal\tUnread")
+	for _, label := range details {
+		fmt.Fprintf(tw, "%s\t%s\t%d\t%d\n", label.Id, label.Name, label.MessagesTotal, label.MessagesUnread)
+	}
+	return tw.Flush()
+}
+
+// labelCache holds the labels.list result so it is fetched once per run.
+var labelCache []*gmail.Label
+
+// mailboxLabels returns the mailbox's labels (without message counts).
+func mailboxLabels(ctx context.Context, srv *gmail.Service) ([]*gmail.Label, error) {
+	if labelCache != nil {
+		return labelCache, nil
+	}
+	retry := newRetryBackOff()
+	list, err := backoff.Retry(ctx, func() (*gmail.ListLabelsResponse, error) {
+		stats.quota.Add(quotaLabelsList)
+		r, err := srv.Users.Labels.List(*mailbox).Do()
+		if err != nil {
+			return r, retry.classify(err)
+		}
+		return r, nil
+	}, backoff.WithBackOff(retry))
+	if err != nil {
+		return nil, fmt.Errorf("unable to list labels: %v", explainMailboxError(err))
+	}
+	labelCache = list.Labels
+	return labelCache, nil
+}
+
+// systemLabels are Gmail's built-in label IDs, which need no lookup.
+var systemLabels = map[string]bool{
+	"INBOX": true, "SPAM": true, "TRASH": true, "UNREAD": true, "STARRED": true,
+	"IMPORTANT": true, "SENT": true, "DRAFT": true, "CHAT": true,
+	"CATEGORY_PERSONAL": true, "CATEGORY_SOCIAL": true, "CATEGORY_PROMOTIONS": true,
+	"CATEGORY_UPDATES": true, "CATEGORY_FORUMS": true,
+}
+
+// systemLabelTerms are the search operators matching Gmail's system labels,
+// which the label: operator does not take.
+var systemLabelTerms = map[string]string{
+	"INBOX": "in:inbox", "SPAM": "in:spam", "TRASH": "in:trash", "UNREAD": "is:unread",
+	"STARRED": "is:starred", "IMPORTANT": "is:important", "SENT": "in:sent",
+	"DRAFT": "in:drafts", "CHAT": "in:chats",
+	"CATEGORY_PERSONAL": "category:primary", "CATEGORY_SOCIAL": "category:social",
+	"CATEGORY_PROMOTIONS": "category:promotions", "CATEGORY_UPDATES": "category:updates",
+	"CATEGORY_FORUMS": "category:forums",
+}
+
+// labelSearchTerm returns the Gmail search operator matching the label with
+// the given ID.
+func labelSearchTerm(ctx context.Context, srv *gmail.Service, id string) (string, error) {
+	if term, ok := systemLabelTerms[id]; ok {
+		return term, nil
+	}
+	list, err := mailboxLabels(ctx, srv)
+	if err != nil {
+		return "", err
+	}
+	for _, label := range list {
+		if label.Id == id {
+			// Search spells the spaces and nesting slashes of label names
+			// as hyphens
+			return "label:" + strings.NewReplacer(" ", "-", "/", "-").Replace(label.Name), nil
+		}
+	}
+	return "", fmt.Errorf("no label with ID %s", id)
+}
+
+// resolveLabelNames replaces each -label value that is not a label ID with
+// the ID of the label with that display name, so users can pass names like
+// "Newsletters". Names match case-insensitively when there is no exact match.
+func resolveLabelNames(ctx context.Context, srv *gmail.Service) error {
+	for i, value := range labels {
+		if systemLabels[value] || value == allMailLabel {
+			continue
+		}
+		list, err := mailboxLabels(ctx, srv)
+		if err != nil {
+			return err
+		}
+
+		var exact, folded []*gmail.Label
+		for _, label := range list {
+			if label.Id == value {
+				exact = []*gmail.Label{label}
+				break
+			}
+			if label.Name == value {
+				exact = append(exact, label)
+			} else if strings.EqualFold(label.Name, value) {
+				folded = append(folded, label)
+			}
+		}
+		matches := exact
+		if len(matches) == 0 {
+			matches = folded
+		}
+
+		switch len(matches) {
+		case 1:
+			if matches[0].Id != value && *debug {
+				log.Printf("Resolved label %q to ID %s", value, matches[0].Id)
+			}
+			labels[i] = matches[0].Id
+		case 0:
+			return fmt.Errorf("no label named %q; available labels: %s", value, labelNames(list))
+		default:
+			return fmt.Errorf("label name %q is ambiguous; available labels: %s", value, labelNames(list))
+		}
+	}
+	return nil
+}
+
+// labelNames lists the label display names for error messages.
+func labelNames(list []*gmail.Label) string {
+	var names []string
+	for _, label := range list {
+		names = append(names, label.Name)
+	}
+	sort.Strings(names)
+	return strings.Join(names, ", ")
+}
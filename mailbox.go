@@ -0,0 +1,26 @@
+package main
+
+import (
+	"errors"
+	"flag"
+	"fmt"
+	"net/http"
+
+	"google.golang.org/api/googleapi"
+)
+
+var mailbox = flag.String("mailbox", "me", "mailbox to count, either me or the address of a mailbox delegated to the signed-in account")
+
+// explainMailboxError rewords permission errors for a delegated -mailbox,
+// since Gmail's own message does not say that delegation is what's missing.
+func explainMailboxError(err error) error {
+	if *mailbox == "me" {
+		return err
+	}
+
+	var apiErr *googleapi.Error
+	if errors.As(err, &apiErr) && (apiErr.Code == http.StatusForbidden || apiErr.Code == http.StatusNotFound) {
+		return fmt.Errorf("no access to mailbox %s; the signed-in account must have been granted delegated access to it: %w", *mailbox, err)
+	}
+	return err
+}
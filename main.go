@@ -2,255 +2,1490 @@ package main
 
 import (
 	"context"
+	"errors"
 	"flag"
 	"fmt"
+	"io"
+	"iter"
 	"log"
+	"math"
 	"math/rand"
 	"os"
 	"sort"
+	"strconv"
+	"strings"
 	"sync"
+	"sync/atomic"
 	"time"
 
 	"github.com/cenkalti/backoff/v5"
 	"golang.org/x/oauth2/google"
 	"google.golang.org/api/gmail/v1"
 	"google.golang.org/api/option"
+	"google.golang.org/api/sheets/v4"
 )
 
 var timeout = flag.Int("timeout", 60, "timeout in seconds")
+var requestTimeout = flag.Int("request-timeout", 30, "timeout in seconds for each individual message fetch attempt (0 = no per-request timeout)")
 var initialDelay = flag.Int("initial-delay", 1000, "max initial delay in milliseconds before starting to fetch messages")
 var days = flag.Int("days", 30, "number of days to look back")
-var workers = flag.Int("workers", 0, "maximum number of concurrent message fetches (0 = unlimited)")
+var workers = flag.Int("workers", 0, "maximum number of concurrent message fetches, shared across -accounts (0 = unlimited, or 20 with -accounts)")
+var minSize = flag.Int64("min-size", 0, "only count messages whose Gmail sizeEstimate is at least this many bytes (sizeEstimate is approximate; 0 = no minimum)")
+var senderDiversity = flag.Bool("sender-diversity", false, "report the number of distinct senders alongside the message count for each day")
+var bySender = flag.Bool("by-sender", false, "report counts and percent of the total per sender address instead of per day; each percent is rounded to one decimal on its own, so they sum to ~100%")
+var byDomain = flag.Bool("by-domain", false, "report counts and percent of the total per sender domain instead of per day; each percent is rounded to one decimal on its own, so they sum to ~100%")
+var hashSenders = flag.Bool("hash-senders", false, "replace sender addresses and domains with a short stable hash in -by-sender/-by-domain output")
+var hashKeepDomain = flag.Bool("hash-keep-domain", false, "with -hash-senders, hash only the local part of each address and keep its domain")
+var retryOnEmpty = flag.Int("retry-on-empty", 0, "if a listing finds no messages at all, wait and list again up to this many times")
+var checkpointPath = flag.String("checkpoint", "", "periodically save progress to this file and resume from it on the next run; the file holds the partial daily counts and hashes of the last page's message IDs")
+var timestamps = flag.Bool("timestamps", false, "print each message's internalDate as an ISO 8601 timestamp, one per line, instead of daily counts")
+var splitLabels = flag.Bool("split-labels", false, "report a separate count column per -label instead of a merged total")
+var pageSize = flag.Int64("page-size", 0, "message IDs per list page, 1-500 (larger values are clamped to 500); smaller pages mean smaller fetch bursts but more list calls (0 = Gmail's default of 100)")
+var earlyExit = flag.Bool("early-exit", false, "stop paging once a fetched message predates the window (assumes Gmail lists newest first, which it does not guarantee)")
+var maxPages = flag.Int("max-pages", 0, "maximum number of message list pages to walk (0 = unlimited)")
 var debug = flag.Bool("debug", false, "enable debug output")
+var utc = flag.Bool("utc", false, "bucket dates by UTC day boundaries instead of the local timezone")
+var timezone = flag.String("timezone", "", "IANA timezone used for date bucketing, e.g. America/New_York (default local)")
+var starred = flag.Bool("starred", false, "only count starred messages (is:starred)")
+var important = flag.Bool("important", false, "only count messages marked important (is:important)")
+var hasAttachment = flag.Bool("has-attachment", false, "only count messages with attachments, where most malicious spam is (has:attachment)")
+var emailsOnly = flag.Bool("emails-only", false, "skip chat messages, which some accounts file under SPAM (-in:chats)")
+var strict = flag.Bool("strict", false, "exit with status 3 after printing the summary if any warning was reported (invalid internalDate, failed fetch, truncated results)")
+var printQuery = flag.Bool("print-query", false, "print the Gmail query that would be run and exit")
+var diagnose = flag.Bool("diagnose", false, "run connectivity and quota checks against Gmail and exit")
+var format = flag.String("format", "text", "output format: text, table, csv, json, jsonl (the json report on one line), html or influx (InfluxDB line protocol)")
+var topDays = flag.Int("top-days", 0, "in text output, list only the N busiest days (in date order) with the rest summed as \"others\"; the total still covers every day")
+var sortBy = flag.String("sort-by", "date", "order of the text and table rows: date, or count (busiest first, ties by date)")
+var weeklySubtotals = flag.Bool("weekly-subtotals", false, "in text output, add a \"Week of YYYY-MM-DD: N\" subtotal after each Monday-to-Sunday week")
+var csvBOM = flag.Bool("csv-bom", false, "prepend a UTF-8 byte order mark to csv output so Excel detects the encoding")
 var cutoffDate string
+var location = time.Local
+
+// emptyRetryDelay is how long -retry-on-empty waits between listings.
+const emptyRetryDelay = 5 * time.Second
+
+// status receives progress and informational output. It is stdout for text
+// output and stderr otherwise, so machine-readable output stays clean.
+var status io.Writer = os.Stdout
+
+// credentialsFile is the OAuth client secret downloaded from Google Cloud
+// Console, used unless -credentials or a credentials env var is set.
+const credentialsFile = "credentials.json"
+
+// stringList is a flag.Value that collects the values of a repeatable flag.
+type stringList []string
+
+func (l *stringList) String() string {
+	return strings.Join(*l, ",")
+}
+
+func (l *stringList) Set(value string) error {
+	*l = append(*l, value)
+	return nil
+}
+
+var labels stringList
+
+func init() {
+	flag.Var(&labels, "label", "Gmail label ID or name to count; repeat for several labels (default SPAM)")
+}
+
+// fetchFields is the messages.get format and header set a run needs.
+type fetchFields struct {
+	format  string
+	headers []string
+}
+
+// requiredFetchFields picks the smallest fetch that covers every active mode
+// and filter. The "minimal" format already carries internalDate and
+// sizeEstimate, so only header-based features move up to "metadata".
+func requiredFetchFields() fetchFields {
+	var headers []string
+	if *senderDiversity || *bySender || *byDomain || *domainTiers || *allowlistPath != "" {
+		headers = append(headers, "From")
+	}
+	if *byReason {
+		headers = append(headers, reasonHeaders...)
+	}
+	if *byListID {
+		headers = append(headers, "List-Id")
+	}
+	if *subjectRegex != "" {
+		headers = append(headers, "Subject")
+	}
+	if name := dateSourceHeader(); name != "" {
+		headers = append(headers, name)
+	}
+	if len(headers) == 0 {
+		return fetchFields{format: "minimal"}
+	}
+	return fetchFields{format: "metadata", headers: headers}
+}
+
+// newGetCall builds the per-message fetch using requiredFetchFields.
+func newGetCall(srv *gmail.Service, messageId string) *gmail.UsersMessagesGetCall {
+	fields := requiredFetchFields()
+	call := srv.Users.Messages.Get(*mailbox, messageId).Format(fields.format)
+	if len(fields.headers) > 0 {
+		call = call.MetadataHeaders(fields.headers...)
+	}
+	return call
+}
+
+// messageDate returns the YYYY-MM-DD bucket for a message's internalDate (or
+// the -date-source header), or false if the message has no usable date.
+func messageDate(m *gmail.Message) (string, bool) {
+	// internalDate is returned as milliseconds since epoch (assumed to be UTC/GMT)
+	internalDateMs := messageTimeMs(m)
+
+	// Safety check for invalid dates
+	if internalDateMs <= 0 {
+		noteUndated(m)
+		if *debug {
+			log.Printf("Warning: Invalid internalDate (%d) for message ID %s", internalDateMs, redactID(m.Id))
+		}
+		return "", false
+	}
+
+	// Create a time.Time object from the UTC epoch milliseconds.
+	// time.UnixMilli converts the UTC epoch milliseconds to a time.Time object
+	// representing that instant, which is then moved into the bucketing timezone.
+	emailTime := time.UnixMilli(internalDateMs).In(location)
+
+	// Format the time to get the date string in YYYY-MM-DD format
+	return emailTime.Format("2006-01-02"), true
+}
+
+// keepMessage reports whether a fetched message passes the message-level
+// filters selected on the command line.
+func keepMessage(m *gmail.Message) bool {
+	if date, ok := messageDate(m); ok && (!inWindow(date) || beforeCutoffTime(messageTimeMs(m))) {
+		// Picked up by the widened query (see buildQuery)
+		return false
+	}
+	if subjectPattern != nil && !subjectMatches(m) {
+		return false
+	}
+	if *allowlistPath != "" && isAllowlisted(m) {
+		allowlisted.Add(1)
+		return false
+	}
+	if *minSize > 0 {
+		if m.SizeEstimate <= 0 {
+			if *debug {
+				log.Printf("Skipping message ID %s with no sizeEstimate", redactID(m.Id))
+			}
+			return false
+		}
+		if m.SizeEstimate < *minSize {
+			return false
+		}
+	}
+	return true
+}
+
+// collectMessages lists the messages for every requested label, keeping each
+// message once even if it carries several of the labels, and dropping
+// messages rejected by keepMessage. With -retry-on-empty, a listing that
+// finds nothing at all is repeated in case Gmail has not indexed new mail yet.
+func collectMessages(ctx context.Context, srv *gmail.Service) ([]*gmail.Message, error) {
+	return collectLabelMessages(ctx, srv, labels)
+}
+
+// collectLabelMessages is collectMessages for an explicit list of labels to
+// list.
+func collectLabelMessages(ctx context.Context, srv *gmail.Service, listed []string) ([]*gmail.Message, error) {
+	for attempt := 1; ; attempt++ {
+		kept, found, err := collectMessagesOnce(ctx, srv, listed)
+		if err != nil {
+			return nil, err
+		}
+		if found > 0 || attempt > *retryOnEmpty {
+			if found == 0 {
+				accountStatusf(ctx, "No spam messages found.\n")
+			}
+			return kept, nil
+		}
+
+		log.Printf("No messages found; retrying in %v (%d of %d)", emptyRetryDelay, attempt, *retryOnEmpty)
+		select {
+		case <-time.After(emptyRetryDelay):
+		case <-ctx.Done():
+			return nil, ctx.Err()
+		}
+	}
+}
+
+// collectMessagesOnce does a single listing for collectMessages, returning the
+// kept messages and how many distinct messages were found before filtering.
+func collectMessagesOnce(ctx context.Context, srv *gmail.Service, listed []string) ([]*gmail.Message, int, error) {
+	var kept []*gmail.Message
+	seen := make(map[string]bool)
+
+	for _, label := range listed {
+		// Get all messages carrying the label
+		messages, err := listSpamMessages(ctx, srv, label)
+		if err != nil {
+			return nil, 0, fmt.Errorf("unable to list %s messages: %v", label, err)
+		}
+
+		for _, m := range messages {
+			if seen[m.Id] {
+				countStats(ctx, func(s *runStats) { s.duplicates.Add(1) })
+				continue
+			}
+			seen[m.Id] = true
+
+			if keepMessage(m) {
+				kept = append(kept, m)
+			} else {
+				countStats(ctx, func(s *runStats) { s.filtered.Add(1) })
+			}
+		}
+	}
+
+	return kept, len(seen), nil
+}
 
 func getSpamCounts(ctx context.Context, srv *gmail.Service) (map[string]int, error) {
+	messages, err := collectMessages(ctx, srv)
+	if err != nil {
+		return nil, err
+	}
+
+	return countMessagesByDate(messages), nil
+}
+
+// countMessagesByDate buckets messages by their internalDate.
+func countMessagesByDate(messages []*gmail.Message) map[string]int {
 	dailyCounts := make(map[string]int)
 
-	// Get all messages in the SPAM folder
-	messages, err := listSpamMessages(ctx, srv)
+	// Process each message to extract internalDate
+	for _, m := range messages {
+		if emailDate, ok := messageDate(m); ok {
+			dailyCounts[emailDate]++
+		}
+	}
+
+	return dailyCounts
+}
+
+// getLabelSpamCounts is like getSpamCounts but keeps each label separate,
+// returning counts keyed by date and then by label. Each message is counted
+// once toward every requested label it actually carries (from its labelIds,
+// which the minimal format includes), so a message in several of the labels
+// counts toward each. The LabelIds list parameter matches messages carrying
+// all of the given labels, so the labels are instead ORed in the search query
+// and listed in a single pass.
+func getLabelSpamCounts(ctx context.Context, srv *gmail.Service) (map[string]map[string]int, error) {
+	var terms []string
+	for _, label := range labels {
+		term, err := labelSearchTerm(ctx, srv, label)
+		if err != nil {
+			return nil, err
+		}
+		terms = append(terms, term)
+		if label == "SPAM" || label == "TRASH" {
+			includeSpamTrash = true
+		}
+	}
+	labelUnion = "{" + strings.Join(terms, " ") + "}"
+
+	messages, err := collectLabelMessages(ctx, srv, []string{allMailLabel})
 	if err != nil {
-		return nil, fmt.Errorf("unable to list spam messages: %v", err)
+		return nil, err
 	}
 
-	if len(messages) == 0 {
-		fmt.Println("No spam messages found.")
-		return dailyCounts, nil
+	requested := make(map[string]bool)
+	for _, label := range labels {
+		requested[label] = true
 	}
 
-	// Process each message to extract internalDate
+	labelCounts := make(map[string]map[string]int)
 	for _, m := range messages {
-		// internalDate is returned as milliseconds since epoch (assumed to be UTC/GMT)
-		internalDateMs := m.InternalDate
-
-		// Safety check for invalid dates
-		if internalDateMs <= 0 {
-			if *debug {
-				log.Printf("Warning: Invalid internalDate (%d) for message ID %s", internalDateMs, m.Id)
-			}
+		emailDate, ok := messageDate(m)
+		if !ok {
 			continue
 		}
+		for _, label := range m.LabelIds {
+			if !requested[label] {
+				continue
+			}
+			if labelCounts[emailDate] == nil {
+				labelCounts[emailDate] = make(map[string]int)
+			}
+			labelCounts[emailDate][label]++
+		}
+	}
+
+	return labelCounts, nil
+}
 
-		// Create a time.Time object from the UTC epoch milliseconds.
-		// time.UnixMilli converts the UTC epoch milliseconds to a time.Time object
-		// representing that instant in the local system timezone.
-		emailTimeLocal := time.UnixMilli(internalDateMs)
+func listSpamMessages(ctx context.Context, srv *gmail.Service, label string) ([]*gmail.Message, error) {
+	var messages []*gmail.Message
 
-		// Format the local time to get the local date string in YYYY-MM-DD format
-		emailDate := emailTimeLocal.Format("2006-01-02")
+	// Collect results, taking no more than -timeout seconds
+	// This is to prevent the program from hanging indefinitely
+	ctx, cancel := context.WithTimeout(ctx, time.Duration(*timeout)*time.Second)
+	defer cancel()
 
-		dailyCounts[emailDate]++
+	for msg, err := range streamSpamMessages(ctx, srv, label) {
+		if ctx.Err() != nil {
+			break
+		}
+		if err != nil {
+			return nil, err
+		}
+		messages = append(messages, msg)
+	}
+	if ctx.Err() != nil {
+		if stoppedByDeadline(ctx) {
+			// Keep what was fetched; the report is marked partial
+			deadlineReached.Store(true)
+			return messages, nil
+		}
+		return nil, fmt.Errorf("timed out waiting for messages")
 	}
 
-	return dailyCounts, nil
+	return messages, nil
 }
 
-func listSpamMessages(ctx context.Context, srv *gmail.Service) ([]*gmail.Message, error) {
-	var messages []*gmail.Message
-	pageToken := ""
-	var limiter chan struct{}
-	if *workers > 0 {
-		limiter = make(chan struct{}, *workers)
+// streamSpamMessages pages through the messages carrying label and yields each
+// message as soon as its fetch completes, in completion order rather than
+// listing order. Fetch goroutines hand messages over an unbuffered channel, so
+// a slow consumer applies backpressure: finished fetches wait for the consumer
+// to read them, while paging and at most -workers in-flight fetches continue.
+// Messages whose fetch fails after retries are skipped (and logged under
+// -debug); a paging failure is yielded as an error and ends the stream.
+// Breaking out of the loop cancels all outstanding work.
+func streamSpamMessages(ctx context.Context, srv *gmail.Service, label string) iter.Seq2[*gmail.Message, error] {
+	return func(yield func(*gmail.Message, error) bool) {
+		ctx, cancel := context.WithCancel(ctx)
+		defer cancel()
+
+		// Create a channel to receive messages
+		msgChan := make(chan *gmail.Message)
+		errChan := make(chan error, 1)
+		go func() {
+			defer close(msgChan)
+			if err := fetchSpamMessages(ctx, srv, label, msgChan); err != nil {
+				errChan <- err
+			}
+		}()
+
+		for msg := range msgChan {
+			if !yield(msg, nil) {
+				return
+			}
+		}
+
+		select {
+		case err := <-errChan:
+			yield(nil, err)
+		default:
+		}
 	}
+}
+
+// fetchSpamMessages walks the message list pages for label, fetching each
+// message in its own goroutine and sending it on out. It returns once every
+// fetch has finished.
+func fetchSpamMessages(ctx context.Context, srv *gmail.Service, label string, out chan<- *gmail.Message) error {
+	pageToken := ""
+	limiter := newFetchLimiter()
 
-	// Create a channel to receive messages
-	msgChan := make(chan *gmail.Message)
 	// Create a WaitGroup to track goroutines
 	var wg sync.WaitGroup
+	defer wg.Wait()
 
-	// Start a goroutine to close channels when all workers are done
-	wg.Add(1)
-	go func() {
-		wg.Wait()
-		close(msgChan)
-	}()
-
-	// Calculate the date 'days' ago
-	query := "after:" + cutoffDate // Gmail query to filter messages
-	fmt.Printf("Gmail query: %s\n", query)
+	query := buildQuery() // Gmail query to filter messages
+	accountStatusf(ctx, "Gmail query: %s\n", describeQuery(label))
+	if *debug {
+		fields := requiredFetchFields()
+		log.Printf("Fetching messages with format %s, headers %v", fields.format, fields.headers)
+	}
 	total := 0
+	pages := 0
+	truncated := false
+	var pageInfos []pageInfo
+	// Paging can repeat an ID on consecutive pages while the mailbox changes
+	seen := make(map[string]bool)
+	// With -early-exit, set once any message older than the window is fetched
+	var olderSeen atomic.Bool
+	windowStart := windowStartMs()
 
 	for {
-		req := srv.Users.Messages.List("me").LabelIds("SPAM").Q(query)
-		if pageToken != "" {
-			req = req.PageToken(pageToken)
+		pageStart := time.Now()
+		r, err := listPage(ctx, srv, label, query, pageToken)
+		if err != nil {
+			finishProgressLine() // keep the count visible above the error
+			return err
 		}
-
-		r, err := backoff.Retry(ctx, func() (*gmail.ListMessagesResponse, error) {
-			// Use exponential backoff to handle rate limiting and transient errors
-			r, err := req.Do()
-
-			if err != nil {
-				if *debug {
-					log.Printf("Error fetching messages: %v", err)
-				}
+		if pages == 0 && *debug {
+			log.Printf("First message list page took %v", time.Since(pageStart).Round(time.Millisecond))
+		}
+		dumpResponse(fmt.Sprintf("page-%s-%04d.json", dumpFileName(label), pages), r)
+		pageInfos = append(pageInfos, pageInfo{
+			index:   pages,
+			ids:     len(r.Messages),
+			hasNext: r.NextPageToken != "",
+			elapsed: time.Since(pageStart),
+		})
+		if pages == 0 {
+			countStats(ctx, func(s *runStats) { s.estimated.Add(r.ResultSizeEstimate) })
+			if err := confirmLargeRun(r.ResultSizeEstimate); err != nil {
+				return err
 			}
-
-			return r, err
-		}, backoff.WithBackOff(backoff.NewExponentialBackOff()))
-		// Check for errors from the backoff retry
-		if err != nil {
-			return nil, fmt.Errorf("error fetching messages: %v", err)
 		}
 
-		// Process messages in parallel
+		// Process messages in parallel. Nothing waits for these fetches until
+		// the deferred wg.Wait, so the next page is listed while this page's
+		// bodies are still in flight and listing latency hides behind them.
 		for _, msg := range r.Messages {
 			messageId := msg.Id
+			if seen[messageId] {
+				countStats(ctx, func(s *runStats) { s.duplicates.Add(1) })
+				if *debug {
+					log.Printf("Skipping message %s already listed on an earlier page", redactID(messageId))
+				}
+				continue
+			}
+			seen[messageId] = true
 			wg.Go(func() {
-				if limiter != nil {
-					limiter <- struct{}{}
-					defer func() {
-						<-limiter
-					}()
+				fullMsg, err := fetchMessage(ctx, srv, messageId, limiter)
+				if err != nil {
+					recordWarning()
+					if *debug {
+						log.Printf("Error fetching message %s: %v", redactID(messageId), err)
+					}
+					return
 				}
 
-				// delay a random interval between 0 and initialDelay milliseconds to avoid hitting rate limits
-				if *initialDelay > 0 {
-					time.Sleep(time.Duration(rand.Intn(*initialDelay)) * time.Millisecond)
+				if *earlyExit && windowStart > 0 && fullMsg.InternalDate > 0 && fullMsg.InternalDate < windowStart {
+					olderSeen.Store(true)
 				}
 
-				fullMsg, err := backoff.Retry(ctx, func() (*gmail.Message, error) {
-					// Fetch the full message using exponential backoff
-					result, err := srv.Users.Messages.Get("me", messageId).Format("minimal").Do()
-					if err != nil {
-						if *debug {
-							log.Printf("Error fetching message %s: %v", messageId, err)
-						}
+				select {
+				case out <- fullMsg:
+				case <-ctx.Done():
+					// The consumer has gone; ctx.Done keeps this from blocking
+					// forever so wg.Wait always returns
+					noteDropped(ctx)
+					if *debug {
+						log.Printf("Dropping message %s: listing cancelled", redactID(messageId))
 					}
-					return result, err
-
-				}, backoff.WithBackOff(backoff.NewExponentialBackOff()))
-				if err == nil {
-					msgChan <- fullMsg
-				} else if *debug {
-					log.Printf("Error fetching message %s: %v", messageId, err)
 				}
 			})
 			total++
-			fmt.Printf("\r%d", total)
+			showFetchProgress(ctx, total)
 		}
 
+		pages++
 		pageToken = r.NextPageToken
 		if pageToken == "" {
 			break
 		}
+		if olderSeen.Load() {
+			// Newest-first listing means later pages are older still. Fetches
+			// run ahead of this check, so it may take an extra page to notice.
+			if *debug {
+				log.Printf("-early-exit: stopping after page %d, which reached messages older than the window", pages)
+			}
+			break
+		}
+		if *maxPages > 0 && pages >= *maxPages {
+			truncated = true
+			break
+		}
 	}
 
-	fmt.Print("\r") // erase the in progress count
-	wg.Done()
+	if accountFrom(ctx) == nil {
+		clearProgress() // erase the in progress count
+	}
+	if *debug {
+		printPageTable(label, pageInfos)
+	}
+	if truncated {
+		recordWarning()
+		log.Printf("Reached the -max-pages limit of %d pages; results are truncated", *maxPages)
+	}
 
-	// Collect results, taking no more than 60 seconds
-	// This is to prevent the program from hanging indefinitely
-	timeout := time.After(time.Duration(*timeout) * time.Second)
-	for {
-		select {
-		case msg, ok := <-msgChan:
-			if !ok {
-				msgChan = nil
-			} else {
-				messages = append(messages, msg)
-			}
-		case <-timeout:
-			return nil, fmt.Errorf("timed out waiting for messages")
+	return nil
+}
+
+// windowStartMs returns the start of the window in epoch milliseconds, or 0
+// when the window is unbounded (-all).
+func windowStartMs() int64 {
+	if !cutoffTime.IsZero() {
+		return cutoffTime.UnixMilli()
+	}
+	start, err := time.ParseInLocation("2006-01-02", cutoffDate, location)
+	if err != nil {
+		return 0
+	}
+	return start.UnixMilli()
+}
+
+// newFetchLimiter returns a semaphore capping concurrent fetches at -workers,
+// the one shared by all of -accounts, or nil when fetches are unlimited.
+func newFetchLimiter() chan struct{} {
+	if fetchAIMD != nil {
+		// -adaptive does the limiting for message fetches
+		return nil
+	}
+	if sharedFetchLimiter != nil {
+		return sharedFetchLimiter
+	}
+	if *workers > 0 {
+		return make(chan struct{}, *workers)
+	}
+	return nil
+}
+
+// maxPageSize is the largest maxResults Gmail honors for a message list.
+const maxPageSize = 500
+
+// clampPageSize brings -page-size within what Gmail accepts, warning rather
+// than letting the API clamp it silently. Negative values fall back to
+// Gmail's default page size.
+func clampPageSize(size int64) int64 {
+	switch {
+	case size > maxPageSize:
+		log.Printf("WARNING: -page-size %d is above Gmail's limit; using %d", size, maxPageSize)
+		return maxPageSize
+	case size < 0:
+		log.Printf("WARNING: -page-size %d is below 1; using Gmail's default page size", size)
+		return 0
+	}
+	return size
+}
+
+// listPage fetches one page of message IDs for label, starting at pageToken.
+func listPage(ctx context.Context, srv *gmail.Service, label, query, pageToken string) (*gmail.ListMessagesResponse, error) {
+	req := srv.Users.Messages.List(*mailbox).Q(query)
+	if label != allMailLabel {
+		req = req.LabelIds(label)
+	}
+	if *pageSize > 0 {
+		req = req.MaxResults(*pageSize)
+	}
+	if includeSpamTrash {
+		req = req.IncludeSpamTrash(true)
+	}
+	if pageToken != "" {
+		req = req.PageToken(pageToken)
+	}
+
+	attempts := 0
+	retry := newRetryBackOff()
+	r, err := backoff.Retry(ctx, func() (*gmail.ListMessagesResponse, error) {
+		// Use exponential backoff to handle rate limiting and transient errors
+		if attempts++; attempts > 1 {
+			countStats(ctx, func(s *runStats) { s.retries.Add(1) })
 		}
+		if err := awaitRequestSlot(ctx); err != nil {
+			return nil, backoff.Permanent(err)
+		}
+		countStats(ctx, func(s *runStats) { s.quota.Add(quotaMessagesList) })
+		r, err := req.Do()
 
-		if msgChan == nil {
-			break
+		if err != nil {
+			if *debug {
+				log.Printf("Error fetching messages: %v", err)
+			}
+			return r, retry.classify(err)
 		}
+
+		return r, nil
+	}, backoff.WithBackOff(retry))
+	// Check for errors from the backoff retry
+	if err != nil {
+		return nil, fmt.Errorf("error fetching messages: %v", explainMailboxError(err))
 	}
 
-	return messages, nil
+	countStats(ctx, func(s *runStats) { s.pages.Add(1) })
+	countStats(ctx, func(s *runStats) { s.listed.Add(int64(len(r.Messages))) })
+	return r, nil
 }
 
-type outputStates int
+// fetchMessage fetches a single message, waiting for a slot in limiter (if
+// any) and retrying transient failures with exponential backoff.
+func fetchMessage(ctx context.Context, srv *gmail.Service, messageId string, limiter chan struct{}) (*gmail.Message, error) {
+	if limiter != nil {
+		limiter <- struct{}{}
+		defer func() {
+			<-limiter
+		}()
+	}
+	if fetchAIMD != nil {
+		if err := fetchAIMD.acquire(ctx); err != nil {
+			return nil, err
+		}
+		defer fetchAIMD.release()
+	}
+
+	// delay a random interval between 0 and initialDelay milliseconds to avoid hitting rate limits
+	if *initialDelay > 0 {
+		time.Sleep(time.Duration(rand.Intn(*initialDelay)) * time.Millisecond)
+	}
 
-const (
-	FirstLine outputStates = iota
-	BeforeDate
-	OnOrAfterDate
-)
+	attempts := 0
+	retry := newRetryBackOff()
+	msg, err := backoff.Retry(ctx, func() (*gmail.Message, error) {
+		// Fetch the full message using exponential backoff, bounding each
+		// attempt separately from the overall -timeout
+		if attempts++; attempts > 1 {
+			countStats(ctx, func(s *runStats) { s.retries.Add(1) })
+		}
+		if err := awaitRequestSlot(ctx); err != nil {
+			return nil, backoff.Permanent(err)
+		}
+		countStats(ctx, func(s *runStats) { s.quota.Add(quotaMessagesGet) })
+		reqCtx, cancel := ctx, context.CancelFunc(func() {})
+		if *requestTimeout > 0 {
+			reqCtx, cancel = context.WithTimeout(ctx, time.Duration(*requestTimeout)*time.Second)
+		}
+		result, err := newGetCall(srv, messageId).Context(reqCtx).Do()
+		timedOut := errors.Is(reqCtx.Err(), context.DeadlineExceeded) && ctx.Err() == nil
+		cancel()
+		if err != nil {
+			if timedOut {
+				err = &requestTimeoutError{id: messageId, after: time.Duration(*requestTimeout) * time.Second}
+			}
+			if *debug {
+				log.Printf("Error fetching message %s: %v", redactID(messageId), err)
+			}
+			if fetchAIMD != nil && isRateLimited(err) {
+				fetchAIMD.throttled()
+			}
+			return result, retry.classify(err)
+		}
+		if fetchAIMD != nil {
+			fetchAIMD.succeeded()
+		}
+		return result, nil
+
+	}, backoff.WithBackOff(retry))
+	if err != nil {
+		countStats(ctx, func(s *runStats) { s.failures.Add(1) })
+		return nil, err
+	}
+
+	countStats(ctx, func(s *runStats) { s.fetched.Add(1) })
+	dumpResponse("message-"+dumpFileName(redactID(messageId))+".json", msg)
+	return msg, nil
+}
 
 func printSpamSummary(spamCounts map[string]int) {
-	var dates []string
-	for date := range spamCounts {
-		dates = append(dates, date)
+	dates := reportDates(spamCounts)
+
+	// The footer always reports every day, even when -top-days hides some
+	grandTotal := 0
+	for _, count := range spamCounts {
+		grandTotal += count
+	}
+	if *topDays > 0 {
+		dates = busiestDates(spamCounts, *topDays)
+		if *sortBy == "count" {
+			sortByCount(dates, spamCounts)
+		}
 	}
-	sort.Strings(dates)
 
 	total := 0
-	outputState := FirstLine
+	week, weekTotal := "", 0
 	for _, date := range dates {
-		if date < cutoffDate {
-			outputState = BeforeDate
-			// log.Default().Printf("Switching to BEFORE_DATE for date: %s\n", date)
-		} else {
-			if outputState == BeforeDate {
-				// Print a blank line to separate sections
-				fmt.Println()
+		count := spamCounts[date]
+		total += count
+		dateValue, err := time.Parse("2006-01-02", date)
+		if err != nil {
+			log.Printf("Error parsing date: %v", err)
+			continue
+		}
+		if *weeklySubtotals {
+			// Comparing week starts rather than adjacent dates keeps the
+			// boundaries right when whole days have no spam
+			if start := weekStart(dateValue); start != week {
+				if week != "" {
+					printWeekSubtotal(week, weekTotal)
+				}
+				week, weekTotal = start, 0
 			}
-			outputState = OnOrAfterDate
+			weekTotal += count
 		}
+		fmt.Println(expandTemplate(*rowTemplate, map[string]string{
+			"date":    displayDate(dateValue),
+			"weekday": dateValue.Format("Mon"),
+			"count":   strconv.Itoa(count),
+			"total":   strconv.Itoa(total),
+		}))
+	}
+	if week != "" {
+		printWeekSubtotal(week, weekTotal)
+	}
+	if *topDays > 0 && len(dates) < len(spamCounts) {
+		fmt.Printf("(others: %d)\n", grandTotal-total)
+	}
+	fmt.Println(expandTemplate(*footerTemplate, map[string]string{"total": strconv.Itoa(grandTotal)}))
+	printSpamFreeStreak(spamCounts)
+	fmt.Printf("Average per day: %.1f\n", averagePerDay(grandTotal))
+	printUndatedNote()
+}
 
-		count := spamCounts[date]
-		total += count
+// reportDates returns the dates of spamCounts in -sort-by order.
+func reportDates(spamCounts map[string]int) []string {
+	var dates []string
+	for date := range spamCounts {
+		dates = append(dates, date)
+	}
+	if *sortBy == "count" {
+		sortByCount(dates, spamCounts)
+	} else {
+		sort.Strings(dates)
+	}
+	return dates
+}
+
+// sortByCount orders dates busiest first, with ties going to the earlier
+// date.
+func sortByCount(dates []string, spamCounts map[string]int) {
+	sort.Slice(dates, func(i, j int) bool {
+		if spamCounts[dates[i]] != spamCounts[dates[j]] {
+			return spamCounts[dates[i]] > spamCounts[dates[j]]
+		}
+		return dates[i] < dates[j]
+	})
+}
+
+// busiestDates returns the n dates with the most spam, in date order. Ties
+// go to the earlier date.
+func busiestDates(spamCounts map[string]int, n int) []string {
+	var dates []string
+	for date := range spamCounts {
+		dates = append(dates, date)
+	}
+	sortByCount(dates, spamCounts)
+	dates = dates[:min(n, len(dates))]
+	sort.Strings(dates)
+	return dates
+}
+
+// weekStart returns the Monday on or before date as YYYY-MM-DD. Dates are
+// already bucketed in the report timezone, so no conversion is needed.
+func weekStart(date time.Time) string {
+	offset := (int(date.Weekday()) + 6) % 7 // days since Monday
+	return date.AddDate(0, 0, -offset).Format("2006-01-02")
+}
+
+func printWeekSubtotal(week string, count int) {
+	fmt.Printf("Week of %s: %d\n", week, count)
+	fmt.Println()
+}
+
+// printUndatedNote explains why the total may be lower than the number of
+// fetched messages.
+func printUndatedNote() {
+	if n := undatedCount.Load(); n > 0 {
+		fmt.Printf("Not counted: %d messages with no internalDate\n", n)
+	}
+}
+
+// printLabelSpamSummary prints one row per date with a count column per label.
+func printLabelSpamSummary(labelCounts map[string]map[string]int) {
+	var dates []string
+	for date := range labelCounts {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	fmt.Printf("%-14s", "Date")
+	for _, label := range labels {
+		fmt.Printf(" %s", label)
+	}
+	fmt.Println()
+
+	totals := make(map[string]int)
+	for _, date := range dates {
 		dateValue, err := time.Parse("2006-01-02", date)
 		if err != nil {
 			log.Printf("Error parsing date: %v", err)
 			continue
 		}
-		dayOfWeek := dateValue.Format("Mon")
-		fmt.Printf("%s %s %d\n", dayOfWeek, date, count)
+		fmt.Printf("%s %s", dateValue.Format("Mon"), date)
+		for _, label := range labels {
+			count := labelCounts[date][label]
+			totals[label] += count
+			fmt.Printf(" %*d", len(label), count)
+		}
+		fmt.Println()
+	}
+
+	fmt.Printf("%-14s", "Total:")
+	for _, label := range labels {
+		fmt.Printf(" %*d", len(label), totals[label])
+	}
+	fmt.Println()
+}
+
+// printHeader prints the report heading, noting any active query filters.
+func printHeader(title string) {
+	partial := ""
+	if deadlineReached.Load() {
+		partial = " (deadline reached, partial results)"
+	}
+	if noAuthPlaceholder {
+		partial = " (no-auth placeholder, nothing was counted)"
+	}
+	fmt.Printf("%s%s%s:\n", title, filterSummary(), partial)
+	if *searchAll {
+		fmt.Println("NOTE: -search-all counts whatever matches -query across all mail, not Gmail's spam classifier")
+	}
+}
+
+// maxSeconds is the longest timeout, in seconds, that fits in a
+// time.Duration.
+const maxSeconds = math.MaxInt64 / int64(time.Second)
+
+// validateSeconds checks a timeout flag given in seconds, which would
+// otherwise overflow when converted to a time.Duration.
+func validateSeconds(name string, seconds int) error {
+	if seconds <= 0 || int64(seconds) > maxSeconds {
+		return fmt.Errorf("-%s must be between 1 and %d seconds", name, maxSeconds)
+	}
+	return nil
+}
+
+// validateModes rejects combinations of mutually exclusive output modes.
+func validateModes() error {
+	modes := []struct {
+		name string
+		on   bool
+	}{
+		{"-split-labels", *splitLabels},
+		{"-sender-diversity", *senderDiversity},
+		{"-by-sender", *bySender},
+		{"-by-domain", *byDomain},
+		{"-domain-tiers", *domainTiers},
+		{"-by-reason", *byReason},
+		{"-by-list-id", *byListID},
+		{"-by-daypart", *byDaypart},
+		{"-timestamps", *timestamps},
+	}
+
+	var active []string
+	for _, mode := range modes {
+		if mode.on {
+			active = append(active, mode.name)
+		}
+	}
+	if len(active) > 1 {
+		return fmt.Errorf("%s are mutually exclusive", strings.Join(active, " and "))
+	}
+	if *checkpointPath != "" && len(active) > 0 {
+		return fmt.Errorf("-checkpoint cannot be combined with %s", active[0])
+	}
+	if *format == "influx" && len(active) > 0 {
+		return fmt.Errorf("-format influx cannot be combined with %s", active[0])
+	}
+	if (*format == "json" || *format == "jsonl" || *format == "table") && len(active) > 0 && !*bySender && !*byDomain {
+		return fmt.Errorf("-format %s cannot be combined with %s", *format, active[0])
+	}
+	if *format == "html" && len(active) > 0 && !*bySender && !*byDomain {
+		return fmt.Errorf("-format html cannot be combined with %s", active[0])
+	}
+	if *compact && *format != "text" {
+		return fmt.Errorf("-compact cannot be combined with -format %s", *format)
+	}
+	if *baselinePath != "" && (*format != "text" || *compact || *weeklySubtotals || len(active) > 0) {
+		return fmt.Errorf("-baseline only applies to the default daily text output")
+	}
+	if *topDays < 0 {
+		return fmt.Errorf("-top-days must not be negative")
+	}
+	if *topDays > 0 && (*format != "text" || *compact || *weeklySubtotals || *baselinePath != "" || len(active) > 0) {
+		return fmt.Errorf("-top-days only applies to the default daily text output")
+	}
+	if *weeklySubtotals && (*format != "text" || *compact || len(active) > 0) {
+		return fmt.Errorf("-weekly-subtotals only applies to the default daily text output")
+	}
+	if *sortBy != "date" && *sortBy != "count" {
+		return fmt.Errorf("unknown -sort-by %q: want date or count", *sortBy)
+	}
+	if *sortBy == "count" && (*weeklySubtotals || *baselinePath != "") {
+		return fmt.Errorf("-sort-by count cannot be combined with -weekly-subtotals or -baseline")
+	}
+	if *compact && len(active) > 0 {
+		return fmt.Errorf("-compact cannot be combined with %s", active[0])
+	}
+	if (*csvOut != "" || *jsonOut != "") && len(active) > 0 {
+		return fmt.Errorf("-csv-out and -json-out cannot be combined with %s", active[0])
 	}
-	fmt.Printf("Total: %d\n", total)
+	if *webhookURL != "" && len(active) > 0 {
+		return fmt.Errorf("-webhook cannot be combined with %s", active[0])
+	}
+	if *sheetID != "" && len(active) > 0 {
+		return fmt.Errorf("-sheet-id cannot be combined with %s", active[0])
+	}
+	if *onChangePath != "" && len(active) > 0 {
+		return fmt.Errorf("-on-change cannot be combined with %s", active[0])
+	}
+	if *mergeFrom != "" && (len(active) > 0 || *sheetID != "" || *deleteSpam || *useIMAP || *checkpointPath != "") {
+		return fmt.Errorf("-merge works offline and only supports the daily outputs")
+	}
+	if *tolerateMissingCreds && (len(active) > 0 || *deleteSpam) {
+		return fmt.Errorf("-tolerate-missing-creds only supports the daily outputs")
+	}
+	if *tolerateMissingCreds && (*format == "csv" || *format == "html" || *format == "influx" || *csvOut != "") {
+		// These have nowhere to carry the no-auth placeholder marker
+		return fmt.Errorf("-tolerate-missing-creds only supports -format text, table, json or jsonl, without -csv-out")
+	}
+	if *deleteSpam && len(active) > 0 {
+		return fmt.Errorf("-delete cannot be combined with %s", active[0])
+	}
+	if *deleteSpam && *deadline > 0 {
+		return fmt.Errorf("-delete cannot be combined with -deadline")
+	}
+	if *deleteSpam && *checkpointPath != "" {
+		return fmt.Errorf("-delete cannot be combined with -checkpoint")
+	}
+	if *dryRun && !*deleteSpam {
+		return fmt.Errorf("-dry-run requires -delete")
+	}
+	return nil
+}
+
+// resolveLocation returns the timezone used for date bucketing based on the
+// -utc and -timezone flags.
+func resolveLocation() (*time.Location, error) {
+	if *utc && *timezone != "" {
+		return nil, fmt.Errorf("-utc and -timezone are mutually exclusive")
+	}
+	if *utc {
+		return time.UTC, nil
+	}
+	if *timezone != "" {
+		loc, err := time.LoadLocation(*timezone)
+		if err != nil {
+			return nil, fmt.Errorf("invalid timezone %q: %v", *timezone, err)
+		}
+		return loc, nil
+	}
+	return time.Local, nil
 }
 
 func main() {
-	flag.Parse()
-	cutoffDate = time.Now().AddDate(0, 0, -*days).Format("2006-01-02")
+	// Every path out of main records -status-file: fatalf and the exits
+	// below write it themselves, this covers returns and panics.
+	defer func() {
+		if r := recover(); r != nil {
+			writeStatusFile(fmt.Errorf("panic: %v", r))
+			panic(r)
+		}
+		writeStatusFile(nil)
+	}()
+	command, err := parseCommandLine()
+	if err != nil {
+		fatalf("Invalid arguments: %v", err)
+	}
+	log.SetOutput(progressSafeWriter{os.Stderr})
+	loc, err := resolveLocation()
+	if err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	location = loc
+	switch *format {
+	case "text", "table":
+	case "csv", "json", "jsonl", "html", "influx":
+		status = os.Stderr
+	default:
+		fatalf("Invalid flags: unknown -format %q", *format)
+	}
+	if err := validateDateFormat(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if err := validateTemplates(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if err := validateDateSource(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if err := validateGranularity(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if err := compileSubjectRegex(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if *domainTiers {
+		if err := parseTierThresholds(); err != nil {
+			fatalf("Invalid flags: %v", err)
+		}
+	}
+	if *byDaypart {
+		if err := parseDaypartBreaks(); err != nil {
+			fatalf("Invalid flags: %v", err)
+		}
+	}
+	if err := prepareDumpDir(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if err := validateModes(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	*pageSize = clampPageSize(*pageSize)
+	if err := validateIMAP(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if err := validateWebhook(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if *noTokenPersist && *noInteractive {
+		fatalf("Invalid flags: -no-token-persist always needs interactive consent, so it cannot be combined with -no-interactive or auth-check")
+	}
+	if *noTokenPersist && *useKeyring {
+		fatalf("Invalid flags: -no-token-persist keeps the token in memory, so it cannot be combined with -keyring")
+	}
+	if *allowlistPath != "" {
+		if err := loadAllowlist(*allowlistPath); err != nil {
+			fatalf("Invalid flags: %v", err)
+		}
+	}
+	if *baselinePath != "" {
+		if err := loadBaseline(*baselinePath); err != nil {
+			fatalf("Invalid flags: %v", err)
+		}
+	}
+	if err := validateSearchAll(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if err := validateAccounts(); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if *searchAll {
+		labels = stringList{allMailLabel}
+	} else if len(labels) == 0 {
+		labels = stringList{"SPAM"}
+	}
+	if err := resolveWindow(time.Now()); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+
+	if *explain {
+		fmt.Println(explanation())
+		// An explicit count subcommand runs after the explanation
+		if command != "count" {
+			return
+		}
+	}
 
-	ctx := context.Background()
-	b, err := os.ReadFile("credentials.json") // Download from Google Cloud Console
+	if *mergeFrom != "" {
+		paths, err := mergeReportPaths(*mergeFrom)
+		if err != nil {
+			fatalf("Invalid flags: %v", err)
+		}
+		spamCounts, err := mergeReports(paths)
+		if err != nil {
+			fatalf("Error merging reports: %v", err)
+		}
+		setMergedWindow(spamCounts, len(paths))
+		printReport(context.Background(), spamCounts)
+		return
+	}
+
+	if *printQuery {
+		for _, label := range labels {
+			fmt.Printf("Gmail query: %s\n", describeQuery(label))
+		}
+		return
+	}
+
+	ctx, err := withProxy(context.Background())
 	if err != nil {
-		log.Fatalf("Unable to read client secret file: %v", err)
+		fatalf("Invalid flags: %v", err)
+	}
+	ctx, cancel := withDeadline(ctx)
+	defer cancel()
+	if err := validateSeconds("timeout", *timeout); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if err := validateSeconds("auth-timeout", *authTimeout); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+	if *rps < 0 {
+		fatalf("Invalid flags: -rps must not be negative")
+	}
+	if *rps > 0 {
+		requestLimiter = newRateLimiter(*rps)
+	}
+	if *adaptive {
+		fetchAIMD = newAIMDLimiter(adaptiveCeiling)
+		if *workers > 0 {
+			fetchAIMD = newAIMDLimiter(*workers)
+		}
+	}
+	if *diagnose {
+		if !runDiagnostics(ctx) {
+			writeStatusFile(errors.New("diagnostics failed"))
+			os.Exit(1)
+		}
+		return
 	}
 
-	// If modifying these scopes, delete your previously saved token.json.
-	config, err := google.ConfigFromJSON(b, gmail.GmailReadonlyScope)
+	if *useIMAP {
+		spamCounts, err := getIMAPSpamCounts(ctx)
+		if err != nil {
+			fatalf("Error getting spam counts over IMAP: %v", err)
+		}
+		printReport(ctx, spamCounts)
+		return
+	}
+
+	b, err := readCredentials()
+	if errors.Is(err, os.ErrNotExist) && *tolerateMissingCreds {
+		log.Printf("WARNING: no client secret found (%v); -tolerate-missing-creds is set, so the report is an empty no-auth placeholder", err)
+		noAuthPlaceholder = true
+		printReport(ctx, map[string]int{})
+		return
+	}
 	if err != nil {
-		log.Fatalf("Unable to parse client secret file to config: %v", err)
+		fatalf("Unable to read client secret: %v", err)
+	}
+	if err := validateCredentials(b); err != nil {
+		fatalf("Unable to use client secret: %v", err)
+	}
+
+	config, err := google.ConfigFromJSON(b, runScopes()...)
+	if err != nil {
+		fatalf("Unable to parse client secret file to config: %v", err)
+	}
+	applyAuthBaseURL(config)
+	if usesOOBRedirect(config) {
+		log.Printf("WARNING: %s.", oobAdvice)
+	}
+	if len(accountList()) > 0 {
+		runAccountsReport(ctx, config)
+		finishRun()
+		return
+	}
+	client := getClient(ctx, config, tokenPath(config))
+	if authCheck {
+		fmt.Println("Cached token is valid")
+		return
 	}
-	client := getClient(ctx, config)
 
 	srv, err := gmail.NewService(ctx, option.WithHTTPClient(client))
 	if err != nil {
-		log.Fatalf("Unable to retrieve Gmail client: %v", err)
+		fatalf("Unable to retrieve Gmail client: %v", err)
+	}
+
+	if *sheetID != "" {
+		sheetsService, err = sheets.NewService(ctx, option.WithHTTPClient(client))
+		if err != nil {
+			fatalf("Unable to retrieve Sheets client: %v", err)
+		}
+	}
+
+	if *warmup {
+		warmUpConnection(ctx, srv)
+	}
+
+	if *listLabels {
+		if err := printLabels(ctx, srv); err != nil {
+			fatalf("Error listing labels: %v", err)
+		}
+		return
+	}
+
+	if err := resolveLabelNames(ctx, srv); err != nil {
+		fatalf("Invalid flags: %v", err)
+	}
+
+	stopHeartbeat := startHeartbeat()
+	runReport(ctx, srv)
+	stopHeartbeat()
+	finishRun()
+}
+
+// finishRun reports the run's side notes and exits with the partial or
+// warnings status when the run ended that way.
+func finishRun() {
+	if n := allowlisted.Load(); n > 0 {
+		fmt.Fprintf(status, "Excluded %d messages from allowlisted senders\n", n)
+	}
+
+	if *showStats {
+		printStats()
+	}
+
+	if n := droppedMessages.Load(); n > 0 {
+		log.Printf("WARNING: %d fetched messages were dropped when the listing was cancelled; results may be incomplete", n)
+	}
+
+	if deadlineReached.Load() {
+		log.Printf("-deadline of %v reached: results are partial", *deadline)
+		writeStatusFile(errDeadlineReached)
+		os.Exit(exitPartial)
+	}
+
+	if *strict && warningCount() > 0 {
+		log.Printf("-strict: %d warnings were reported during the run", warningCount())
+		writeStatusFile(fmt.Errorf("-strict: %d warnings were reported during the run", warningCount()))
+		os.Exit(exitWarnings)
+	}
+}
+
+// runReport fetches the messages and prints the report for the selected mode.
+func runReport(ctx context.Context, srv *gmail.Service) {
+	if *timestamps {
+		times, err := getSpamTimestamps(ctx, srv)
+		if err != nil {
+			fatalf("Error getting spam timestamps: %v", err)
+		}
+
+		reportedTotal = len(times)
+		printTimestamps(times)
+		return
+	}
+
+	if *byDaypart {
+		times, err := getSpamTimestamps(ctx, srv)
+		if err != nil {
+			fatalf("Error getting spam counts: %v", err)
+		}
+
+		reportedTotal = len(times)
+		printHeader(fmt.Sprintf("Spam email counts by time of day for %s (%s)", windowDescription, location))
+		printDaypartSummary(times)
+		return
+	}
+
+	if *byListID {
+		listCounts, err := getListIDCounts(ctx, srv)
+		if err != nil {
+			fatalf("Error getting spam counts: %v", err)
+		}
+		noteTotal(listCounts)
+
+		printHeader(fmt.Sprintf("Spam email counts by List-Id for %s", windowDescription))
+		printSenderSummary(listCounts)
+		return
+	}
+
+	if *byReason {
+		reasonCounts, err := getReasonCounts(ctx, srv)
+		if err != nil {
+			fatalf("Error getting spam counts: %v", err)
+		}
+		noteTotal(reasonCounts)
+
+		printHeader(fmt.Sprintf("Spam email counts by reason for %s", windowDescription))
+		// Same layout as the per-sender list: count, then key
+		printSenderSummary(reasonCounts)
+		return
+	}
+
+	if *domainTiers {
+		domainCounts, err := getSenderCounts(ctx, srv)
+		if err != nil {
+			fatalf("Error getting spam counts: %v", err)
+		}
+		noteTotal(domainCounts)
+
+		printHeader(fmt.Sprintf("Spam sender domains by volume tier for %s", windowDescription))
+		printDomainTiers(domainCounts)
+		return
+	}
+
+	if *bySender || *byDomain {
+		senderCounts, err := getSenderCounts(ctx, srv)
+		if err != nil {
+			fatalf("Error getting spam counts: %v", err)
+		}
+		noteTotal(senderCounts)
+
+		switch *format {
+		case "csv":
+			if err := writeSenderCSVSummary(os.Stdout, senderCounts); err != nil {
+				fatalf("Error writing csv: %v", err)
+			}
+			return
+		case "html":
+			if err := writeSenderHTMLSummary(os.Stdout, senderCounts); err != nil {
+				fatalf("Error writing html: %v", err)
+			}
+			return
+		case "json":
+			if err := writeSenderJSONSummary(os.Stdout, senderCounts); err != nil {
+				fatalf("Error writing json: %v", err)
+			}
+			return
+		case "jsonl":
+			if err := writeSenderJSONLineSummary(os.Stdout, senderCounts); err != nil {
+				fatalf("Error writing json: %v", err)
+			}
+			return
+		}
+		printHeader(fmt.Sprintf("Spam email counts by %s for %s", senderColumn(), windowDescription))
+		if *format == "table" {
+			printSenderTable(senderCounts)
+			return
+		}
+		printSenderSummary(senderCounts)
+		return
+	}
+
+	if *senderDiversity {
+		diversity, err := getSenderDiversity(ctx, srv)
+		if err != nil {
+			fatalf("Error getting spam counts: %v", err)
+		}
+
+		printHeader(fmt.Sprintf("Spam senders per day for %s (based on internalDate, %s)", windowDescription, location))
+		printSenderDiversity(diversity)
+		return
+	}
+
+	if *splitLabels {
+		labelCounts, err := getLabelSpamCounts(ctx, srv)
+		if err != nil {
+			fatalf("Error getting spam counts: %v", err)
+		}
+
+		if *format == "csv" {
+			if err := writeLabelCSVSummary(os.Stdout, labelCounts); err != nil {
+				fatalf("Error writing csv: %v", err)
+			}
+			return
+		}
+		printHeader(fmt.Sprintf("Spam email counts by label for %s (based on internalDate, %s)", windowDescription, location))
+		printLabelSpamSummary(labelCounts)
+		return
 	}
 
-	spamCounts, err := getSpamCounts(ctx, srv)
+	var spamCounts map[string]int
+	var counted []*gmail.Message
+	var err error
+	if *checkpointPath != "" {
+		spamCounts, err = getCheckpointedSpamCounts(ctx, srv, *checkpointPath)
+	} else if *deleteSpam {
+		counted, err = collectMessages(ctx, srv)
+		spamCounts = countMessagesByDate(counted)
+	} else {
+		spamCounts, err = getSpamCounts(ctx, srv)
+	}
 	if err != nil {
-		log.Fatalf("Error getting spam counts: %v", err)
+		fatalf("Error getting spam counts: %v", err)
 	}
 
-	fmt.Printf("Spam email counts for the past %v days (based on internalDate):\n", *days)
-	printSpamSummary(spamCounts)
+	printReport(ctx, spamCounts)
+
+	if *deleteSpam {
+		if err := trashMessages(ctx, srv, counted); err != nil {
+			fatalf("Error deleting spam: %v", err)
+		}
+	}
+}
+
+// printReport sends the daily counts to every configured output.
+func printReport(ctx context.Context, spamCounts map[string]int) {
+	// Outputs still go out after -deadline has cancelled the fetches
+	ctx = context.WithoutCancel(ctx)
+	if *allMessages {
+		settleAllTimeWindow(spamCounts, time.Now())
+	}
+	noteTotal(spamCounts)
+	// A -tolerate-missing-creds placeholder counted nothing, so it must not
+	// reach -on-change state, syslog or the webhook as if it were real zeros
+	if *onChangePath != "" && !noAuthPlaceholder {
+		changed, err := countsChanged(*onChangePath, spamCounts)
+		if err != nil {
+			fatalf("Error checking -on-change: %v", err)
+		}
+		if !changed {
+			if *debug {
+				log.Printf("Counts unchanged since the previous run; not reporting")
+			}
+			return
+		}
+		defer func() {
+			if err := saveCounts(*onChangePath, spamCounts); err != nil {
+				fatalf("Error saving -on-change counts: %v", err)
+			}
+		}()
+	}
+	defer printReconciliation(spamCounts)
+
+	if *useSyslog && !noAuthPlaceholder {
+		if err := sendSyslogSummary(spamCounts); err != nil {
+			log.Printf("Unable to write to syslog: %v", err)
+		}
+	}
+
+	if err := writeFileOutputs(spamCounts); err != nil {
+		fatalf("Error writing output: %v", err)
+	}
+
+	if sheetsService != nil {
+		if err := appendToSheet(ctx, spamCounts); err != nil {
+			fatalf("Error exporting to Google Sheets: %v", err)
+		}
+	}
+
+	if *webhookURL != "" && !noAuthPlaceholder {
+		if err := postWebhook(ctx, spamCounts); err != nil {
+			recordWarning()
+			log.Printf("Unable to notify webhook: %v", err)
+		}
+	}
+
+	switch *format {
+	case "csv":
+		if err := writeCSVSummary(os.Stdout, spamCounts); err != nil {
+			fatalf("Error writing csv: %v", err)
+		}
+	case "json":
+		if err := writeJSONSummary(os.Stdout, spamCounts); err != nil {
+			fatalf("Error writing json: %v", err)
+		}
+	case "jsonl":
+		if err := writeJSONLineSummary(os.Stdout, spamCounts); err != nil {
+			fatalf("Error writing json: %v", err)
+		}
+	case "html":
+		if err := writeHTMLSummary(os.Stdout, spamCounts); err != nil {
+			fatalf("Error writing html: %v", err)
+		}
+	case "influx":
+		if err := writeInfluxSummary(os.Stdout, spamCounts); err != nil {
+			fatalf("Error writing influx: %v", err)
+		}
+	case "table":
+		printHeader(fmt.Sprintf("Spam email counts for %s (based on internalDate, %s)", windowDescription, location))
+		printSpamTable(spamCounts)
+		if *showPercentiles {
+			printDailyStats(spamCounts)
+		}
+		if *sparkline {
+			printSparkline(spamCounts)
+		}
+	default:
+		if *compact {
+			line := compactSummary(spamCounts)
+			if noAuthPlaceholder {
+				line += " (no-auth placeholder)"
+			}
+			fmt.Println(line)
+			break
+		}
+		printHeader(fmt.Sprintf("Spam email counts for %s (based on internalDate, %s)", windowDescription, location))
+		if *baselinePath != "" {
+			printBaselineComparison(spamCounts)
+			break
+		}
+		printSpamSummary(spamCounts)
+		if *showPercentiles {
+			printDailyStats(spamCounts)
+		}
+		if *sparkline {
+			printSparkline(spamCounts)
+		}
+	}
 }
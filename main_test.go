@@ -0,0 +1,356 @@
+package main
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"net/http"
+	"net/http/httptest"
+	"os"
+	"path/filepath"
+	"slices"
+	"strings"
+	"sync"
+	"sync/atomic"
+	"testing"
+	"time"
+
+	"google.golang.org/api/gmail/v1"
+)
+
+func TestMessageDateWithoutInternalDate(t *testing.T) {
+	before := undatedCount.Load()
+	m := &gmail.Message{Id: "test-no-internal-date"}
+
+	if date, ok := messageDate(m); ok {
+		t.Fatalf("messageDate = %q, true; want false for a message with no internalDate", date)
+	}
+	if got := undatedCount.Load(); got != before+1 {
+		t.Errorf("undatedCount = %d, want %d", got, before+1)
+	}
+
+	// A message seen again, on another label or page, is only counted once
+	messageDate(m)
+	if got := undatedCount.Load(); got != before+1 {
+		t.Errorf("undatedCount after a repeat = %d, want %d", got, before+1)
+	}
+}
+
+func TestMessageDate(t *testing.T) {
+	oldLocation := location
+	t.Cleanup(func() { location = oldLocation })
+	location = time.UTC
+
+	m := &gmail.Message{Id: "test-dated", InternalDate: time.Date(2024, 3, 1, 23, 30, 0, 0, time.UTC).UnixMilli()}
+	if date, ok := messageDate(m); !ok || date != "2024-03-01" {
+		t.Errorf("messageDate = %q, %t; want 2024-03-01, true", date, ok)
+	}
+}
+
+func TestFetchSpamMessagesSkipsRepeatedIDs(t *testing.T) {
+	setFetchTestFlags(t)
+	// Consecutive pages overlap, as they can while the mailbox changes
+	srv := newFakeGmailService(t, &fakeGmail{pages: [][]string{
+		{"a", "b", "c"},
+		{"c", "d"},
+		{"d", "e", "a"},
+	}})
+	duplicates := stats.duplicates.Load()
+
+	out := make(chan *gmail.Message, 10)
+	if err := fetchSpamMessages(context.Background(), srv, "SPAM", out); err != nil {
+		t.Fatal(err)
+	}
+	close(out)
+
+	delivered := make(map[string]int)
+	for m := range out {
+		delivered[m.Id]++
+	}
+	for _, id := range []string{"a", "b", "c", "d", "e"} {
+		if delivered[id] != 1 {
+			t.Errorf("message %s delivered %d times, want once", id, delivered[id])
+		}
+	}
+	if len(delivered) != 5 {
+		t.Errorf("delivered %d distinct messages, want 5: %v", len(delivered), delivered)
+	}
+	if got := stats.duplicates.Load() - duplicates; got != 3 {
+		t.Errorf("stats.duplicates grew by %d, want 3", got)
+	}
+}
+
+func TestRequiredFetchFields(t *testing.T) {
+	tests := []struct {
+		name        string
+		set         func()
+		wantFormat  string
+		wantHeaders []string
+	}{
+		{"default run", func() {}, "minimal", nil},
+		{"by sender", func() { *bySender = true }, "metadata", []string{"From"}},
+		{"date header", func() { *dateSource = "header" }, "metadata", []string{"Date"}},
+		{"subject regex", func() { *subjectRegex = "(?i)invoice" }, "metadata", []string{"Subject"}},
+		{"by sender with date header", func() {
+			*bySender = true
+			*dateSource = "header"
+		}, "metadata", []string{"From", "Date"}},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			oldBySender, oldDateSource, oldSubject := *bySender, *dateSource, *subjectRegex
+			defer func() {
+				*bySender, *dateSource, *subjectRegex = oldBySender, oldDateSource, oldSubject
+			}()
+			tt.set()
+
+			got := requiredFetchFields()
+			if got.format != tt.wantFormat || !slices.Equal(got.headers, tt.wantHeaders) {
+				t.Errorf("requiredFetchFields() = %s %v, want %s %v", got.format, got.headers, tt.wantFormat, tt.wantHeaders)
+			}
+		})
+	}
+}
+
+func TestFetchSpamMessagesCancelDropsUndelivered(t *testing.T) {
+	setFetchTestFlags(t)
+	const perPage = 20
+	var pages [][]string
+	for p := 0; p < 3; p++ {
+		var ids []string
+		for i := 0; i < perPage; i++ {
+			ids = append(ids, fmt.Sprintf("m%d-%d", p, i))
+		}
+		pages = append(pages, ids)
+	}
+	srv := newFakeGmailService(t, &fakeGmail{pages: pages})
+
+	// Cancel after a varying number of deliveries, landing on every page,
+	// so sends race the cancellation at many different points
+	iterations := 300
+	if testing.Short() {
+		iterations = 30
+	}
+	for i := range iterations {
+		cancelAfter := i % (len(pages) * perPage)
+		dropped, fetched := droppedMessages.Load(), stats.fetched.Load()
+
+		ctx, cancel := context.WithCancel(context.Background())
+		out := make(chan *gmail.Message)
+		done := make(chan error, 1)
+		go func() {
+			done <- fetchSpamMessages(ctx, srv, "SPAM", out)
+		}()
+
+		delivered := 0
+		if cancelAfter == 0 {
+			cancel()
+		}
+	receive:
+		for cancelAfter > 0 {
+			select {
+			case <-out:
+				if delivered++; delivered == cancelAfter {
+					break receive
+				}
+			case <-done:
+				t.Fatalf("iteration %d: fetchSpamMessages returned before cancellation", i)
+			}
+		}
+		// The consumer goes away while other fetches are still in flight
+		cancel()
+
+		select {
+		case <-done:
+		case <-time.After(5 * time.Second):
+			t.Fatalf("iteration %d: fetchSpamMessages did not return after cancellation; wg.Wait is stuck", i)
+		}
+		// Every fetched message was either delivered or counted as dropped;
+		// none vanished uncounted and none was counted twice
+		gotDropped := droppedMessages.Load() - dropped
+		gotFetched := stats.fetched.Load() - fetched
+		if int64(delivered)+gotDropped != gotFetched {
+			t.Fatalf("iteration %d (cancel after %d): %d delivered + %d dropped != %d fetched", i, cancelAfter, delivered, gotDropped, gotFetched)
+		}
+	}
+}
+
+func TestFetchSpamMessagesListsNextPageDuringFetches(t *testing.T) {
+	setFetchTestFlags(t)
+	release := make(chan struct{})
+	var releaseOnce sync.Once
+	releaseFetches := func() { releaseOnce.Do(func() { close(release) }) }
+
+	f := &fakeGmail{pages: [][]string{{"a", "b", "c"}, {"d", "e"}}}
+	var fetchesDoneAtList atomic.Int64
+	fetchesDoneAtList.Store(-1)
+	f.onList = func(page int) {
+		if page == 1 {
+			fetchesDoneAtList.Store(f.gets.Load())
+			releaseFetches()
+		}
+	}
+	f.onGet = func(string) {
+		// Hold every fetch until the second page is listed; the timeout keeps
+		// a serialized regression from hanging the test
+		select {
+		case <-release:
+		case <-time.After(5 * time.Second):
+		}
+	}
+	srv := newFakeGmailService(t, f)
+	t.Cleanup(releaseFetches)
+
+	out := make(chan *gmail.Message, 10)
+	if err := fetchSpamMessages(context.Background(), srv, "SPAM", out); err != nil {
+		t.Fatal(err)
+	}
+	close(out)
+
+	if got := fetchesDoneAtList.Load(); got != 0 {
+		t.Errorf("page 2 was listed after %d of page 1's fetches finished, want it listed while all were in flight", got)
+	}
+	if n := len(out); n != 5 {
+		t.Errorf("delivered %d messages, want 5", n)
+	}
+}
+
+func TestValidateSeconds(t *testing.T) {
+	tests := []struct {
+		seconds int
+		ok      bool
+	}{
+		{-1, false},
+		{0, false},
+		{1, true},
+		{int(maxSeconds), true},
+		{int(maxSeconds) + 1, false},
+	}
+	for _, tt := range tests {
+		err := validateSeconds("timeout", tt.seconds)
+		if (err == nil) != tt.ok {
+			t.Errorf("validateSeconds(%d) = %v, want ok %t", tt.seconds, err, tt.ok)
+		}
+	}
+}
+
+func TestReportDates(t *testing.T) {
+	oldSortBy := *sortBy
+	t.Cleanup(func() { *sortBy = oldSortBy })
+	counts := map[string]int{
+		"2024-03-04": 2,
+		"2024-03-01": 5,
+		"2024-03-03": 2,
+		"2024-03-02": 9,
+	}
+
+	*sortBy = "date"
+	if got, want := reportDates(counts), []string{"2024-03-01", "2024-03-02", "2024-03-03", "2024-03-04"}; !slices.Equal(got, want) {
+		t.Errorf("reportDates by date = %v, want %v", got, want)
+	}
+
+	// Ties on count go to the earlier date
+	*sortBy = "count"
+	if got, want := reportDates(counts), []string{"2024-03-02", "2024-03-01", "2024-03-03", "2024-03-04"}; !slices.Equal(got, want) {
+		t.Errorf("reportDates by count = %v, want %v", got, want)
+	}
+}
+
+func TestSortByCountTies(t *testing.T) {
+	counts := map[string]int{"2024-03-05": 1, "2024-03-02": 1, "2024-03-09": 1}
+	dates := []string{"2024-03-09", "2024-03-05", "2024-03-02"}
+	sortByCount(dates, counts)
+	if want := []string{"2024-03-02", "2024-03-05", "2024-03-09"}; !slices.Equal(dates, want) {
+		t.Errorf("sortByCount = %v, want %v", dates, want)
+	}
+}
+
+func TestClampPageSize(t *testing.T) {
+	tests := []struct {
+		size, want int64
+	}{
+		{-1, 0},
+		{0, 0},
+		{1, 1},
+		{maxPageSize, maxPageSize},
+		{maxPageSize + 1, maxPageSize},
+	}
+	for _, tt := range tests {
+		if got := clampPageSize(tt.size); got != tt.want {
+			t.Errorf("clampPageSize(%d) = %d, want %d", tt.size, got, tt.want)
+		}
+	}
+}
+
+func TestPrintReportNoAuthPlaceholder(t *testing.T) {
+	setTestWindow(t)
+	var posts atomic.Int64
+	server := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		posts.Add(1)
+	}))
+	t.Cleanup(server.Close)
+
+	oldPlaceholder, oldOnChange, oldWebhook := noAuthPlaceholder, *onChangePath, *webhookURL
+	t.Cleanup(func() {
+		noAuthPlaceholder, *onChangePath, *webhookURL = oldPlaceholder, oldOnChange, oldWebhook
+	})
+	noAuthPlaceholder = true
+	*onChangePath = filepath.Join(t.TempDir(), "counts.json")
+	*webhookURL = server.URL
+
+	got := captureStdout(t, func() { printReport(context.Background(), map[string]int{}) })
+	if !strings.Contains(got, "(no-auth placeholder, nothing was counted)") {
+		t.Errorf("report is not marked as a placeholder:\n%s", got)
+	}
+	if _, err := os.Stat(*onChangePath); !errors.Is(err, os.ErrNotExist) {
+		t.Errorf("-on-change state was written for a placeholder: %v", err)
+	}
+	if n := posts.Load(); n != 0 {
+		t.Errorf("webhook received %d posts for a placeholder, want none", n)
+	}
+}
+
+func TestValidateModesNoAuthPlaceholderFormats(t *testing.T) {
+	oldTolerate, oldFormat, oldCSVOut := *tolerateMissingCreds, *format, *csvOut
+	t.Cleanup(func() { *tolerateMissingCreds, *format, *csvOut = oldTolerate, oldFormat, oldCSVOut })
+	*tolerateMissingCreds = true
+
+	for _, f := range []string{"text", "table", "json", "jsonl"} {
+		*format = f
+		if err := validateModes(); err != nil {
+			t.Errorf("-format %s with -tolerate-missing-creds: %v", f, err)
+		}
+	}
+	for _, f := range []string{"csv", "html", "influx"} {
+		*format = f
+		if err := validateModes(); err == nil {
+			t.Errorf("-format %s was accepted with -tolerate-missing-creds; it has no placeholder marker", f)
+		}
+	}
+	*format, *csvOut = "text", "counts.csv"
+	if err := validateModes(); err == nil {
+		t.Error("-csv-out was accepted with -tolerate-missing-creds")
+	}
+}
+
+func TestValidateModesSenderFormats(t *testing.T) {
+	oldBySender, oldByReason, oldFormat := *bySender, *byReason, *format
+	t.Cleanup(func() { *bySender, *byReason, *format = oldBySender, oldByReason, oldFormat })
+
+	*bySender = true
+	for _, f := range []string{"text", "table", "csv", "json", "jsonl", "html"} {
+		*format = f
+		if err := validateModes(); err != nil {
+			t.Errorf("-format %s with -by-sender: %v", f, err)
+		}
+	}
+	*format = "influx"
+	if err := validateModes(); err == nil {
+		t.Error("-format influx was accepted with -by-sender")
+	}
+
+	*bySender, *byReason, *format = false, true, "json"
+	if err := validateModes(); err == nil {
+		t.Error("-format json was accepted with -by-reason")
+	}
+}
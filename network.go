@@ -0,0 +1,51 @@
+package main
+
+import (
+	"context"
+	"errors"
+	"flag"
+	"log"
+	"net"
+	"syscall"
+	"time"
+
+	"google.golang.org/api/gmail/v1"
+)
+
+var warmup = flag.Bool("warmup", false, "open the HTTPS connection to Gmail with a cheap profile request before listing messages")
+
+// networkUnreachableMessage replaces raw transport errors when Google's
+// servers cannot be reached at all.
+const networkUnreachableMessage = "Cannot reach Google servers; check your network connection"
+
+// isNetworkUnreachable reports whether err means the request never reached
+// the server: DNS failure, connection refused, or no route to the host.
+func isNetworkUnreachable(err error) bool {
+	var dnsErr *net.DNSError
+	if errors.As(err, &dnsErr) {
+		return true
+	}
+	if errors.Is(err, syscall.ECONNREFUSED) || errors.Is(err, syscall.ENETUNREACH) || errors.Is(err, syscall.EHOSTUNREACH) {
+		return true
+	}
+
+	var opErr *net.OpError
+	return errors.As(err, &opErr) && opErr.Op == "dial"
+}
+
+// warmUpConnection pays the TLS handshake with a lightweight profile request,
+// leaving a pooled connection for the first message list call. Under -debug
+// the warmup time is logged so it can be compared with the first page time.
+func warmUpConnection(ctx context.Context, srv *gmail.Service) {
+	start := time.Now()
+	stats.quota.Add(quotaGetProfile)
+	if _, err := srv.Users.GetProfile(*mailbox).Context(ctx).Do(); err != nil {
+		if *debug {
+			log.Printf("Connection warmup failed: %v", err)
+		}
+		return
+	}
+	if *debug {
+		log.Printf("Connection warmup took %v", time.Since(start).Round(time.Millisecond))
+	}
+}
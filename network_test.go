@@ -0,0 +1,55 @@
+package main
+
+import (
+	"context"
+	"errors"
+	"net"
+	"net/url"
+	"testing"
+	"time"
+)
+
+func TestIsNetworkUnreachableBlackhole(t *testing.T) {
+	// 192.0.2.0/24 is TEST-NET-1: nothing answers there, so the dial either
+	// times out or fails at once when there is no route
+	ctx, cancel := context.WithTimeout(context.Background(), 200*time.Millisecond)
+	defer cancel()
+	var d net.Dialer
+	conn, err := d.DialContext(ctx, "tcp", "192.0.2.1:443")
+	if err == nil {
+		conn.Close()
+		t.Skip("192.0.2.1 unexpectedly accepted a connection")
+	}
+
+	// The HTTP client wraps transport errors in a *url.Error
+	wrapped := &url.Error{Op: "Get", URL: "https://gmail.googleapis.com/", Err: err}
+	if !isNetworkUnreachable(wrapped) {
+		t.Errorf("isNetworkUnreachable(%v) = false, want true", wrapped)
+	}
+}
+
+func TestIsNetworkUnreachableRefused(t *testing.T) {
+	ln, err := net.Listen("tcp", "127.0.0.1:0")
+	if err != nil {
+		t.Fatal(err)
+	}
+	addr := ln.Addr().String()
+	ln.Close()
+
+	_, err = net.DialTimeout("tcp", addr, time.Second)
+	if err == nil {
+		t.Skip("closed port unexpectedly accepted a connection")
+	}
+	if !isNetworkUnreachable(err) {
+		t.Errorf("isNetworkUnreachable(%v) = false, want true", err)
+	}
+}
+
+func TestIsNetworkUnreachableOtherErrors(t *testing.T) {
+	if isNetworkUnreachable(errors.New("googleapi: Error 500: backendError")) {
+		t.Error("isNetworkUnreachable treated a server error as unreachable")
+	}
+	if isNetworkUnreachable(context.Canceled) {
+		t.Error("isNetworkUnreachable treated cancellation as unreachable")
+	}
+}
@@ -0,0 +1,46 @@
+package main
+
+import (
+	"encoding/json"
+	"errors"
+	"flag"
+	"fmt"
+	"maps"
+	"os"
+)
+
+// There is no built-in watch loop, so "iterations" are separate runs (from
+// cron, say) and the previous counts live in a file between them.
+var onChangePath = flag.String("on-change", "", "only report when the daily counts differ from those saved in this file by the previous run (for cron jobs that notify on change)")
+
+// countsChanged reports whether spamCounts differs from the counts saved at
+// path. A missing file counts as a change.
+func countsChanged(path string, spamCounts map[string]int) (bool, error) {
+	b, err := os.ReadFile(path)
+	if errors.Is(err, os.ErrNotExist) {
+		return true, nil
+	}
+	if err != nil {
+		return false, fmt.Errorf("unable to read %s: %v", path, err)
+	}
+
+	var previous map[string]int
+	if err := json.Unmarshal(b, &previous); err != nil {
+		return false, fmt.Errorf("unable to parse %s: %v", path, err)
+	}
+	return !maps.Equal(previous, spamCounts), nil
+}
+
+// saveCounts records spamCounts at path for the next -on-change comparison,
+// renaming a temporary file into place like checkpoint.save.
+func saveCounts(path string, spamCounts map[string]int) error {
+	b, err := json.Marshal(spamCounts)
+	if err != nil {
+		return err
+	}
+	tmp := path + ".tmp"
+	if err := os.WriteFile(tmp, b, 0600); err != nil {
+		return err
+	}
+	return os.Rename(tmp, path)
+}
@@ -0,0 +1,135 @@
+package main
+
+import (
+	"bytes"
+	"flag"
+	"io"
+	"os"
+	"path/filepath"
+	"regexp"
+	"testing"
+	"time"
+)
+
+var updateGolden = flag.Bool("update", false, "rewrite the testdata/golden files from the current output")
+
+// testCounts is a week of fixture counts, 2024-03-01 (a Friday) to
+// 2024-03-07, with gaps so the spam-free streak has something to find.
+var testCounts = map[string]int{
+	"2024-03-01": 3,
+	"2024-03-02": 12,
+	"2024-03-05": 1,
+}
+
+// setTestWindow fixes the query window globals to the week of testCounts in
+// UTC, restoring them when the test ends.
+func setTestWindow(t *testing.T) {
+	t.Helper()
+	oldCutoff, oldEnd, oldDays := cutoffDate, endDate, windowDays
+	oldDescription, oldLocation := windowDescription, location
+	t.Cleanup(func() {
+		cutoffDate, endDate, windowDays = oldCutoff, oldEnd, oldDays
+		windowDescription, location = oldDescription, oldLocation
+	})
+	cutoffDate, endDate, windowDays = "2024-03-01", "2024-03-08", 7
+	windowDescription, location = "2024-03-01 through 2024-03-07", time.UTC
+}
+
+// captureStdout returns what f prints to os.Stdout.
+func captureStdout(t *testing.T, f func()) string {
+	t.Helper()
+	r, w, err := os.Pipe()
+	if err != nil {
+		t.Fatal(err)
+	}
+	stdout := os.Stdout
+	os.Stdout = w
+	defer func() { os.Stdout = stdout }()
+
+	done := make(chan []byte)
+	go func() {
+		b, _ := io.ReadAll(r)
+		done <- b
+	}()
+	f()
+	w.Close()
+	return string(<-done)
+}
+
+// checkGolden compares got with testdata/golden/name, or rewrites the file
+// under -update.
+func checkGolden(t *testing.T, name, got string) {
+	t.Helper()
+	path := filepath.Join("testdata", "golden", name)
+	if *updateGolden {
+		if err := os.WriteFile(path, []byte(got), 0644); err != nil {
+			t.Fatal(err)
+		}
+		return
+	}
+	want, err := os.ReadFile(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if got != string(want) {
+		t.Errorf("output differs from %s (rerun with -update to accept):\ngot:\n%s\nwant:\n%s", path, got, want)
+	}
+}
+
+func TestTextSummaryGolden(t *testing.T) {
+	setTestWindow(t)
+	got := captureStdout(t, func() { printSpamSummary(testCounts) })
+	checkGolden(t, "summary.txt", got)
+}
+
+func TestTableSummaryGolden(t *testing.T) {
+	setTestWindow(t)
+	got := captureStdout(t, func() { printSpamTable(testCounts) })
+	checkGolden(t, "summary_table.txt", got)
+}
+
+func TestCSVSummaryGolden(t *testing.T) {
+	setTestWindow(t)
+	var b bytes.Buffer
+	if err := writeCSVSummary(&b, testCounts); err != nil {
+		t.Fatal(err)
+	}
+	checkGolden(t, "summary.csv", b.String())
+}
+
+// generatedAt matches the one field of the JSON report that changes per run.
+var generatedAt = regexp.MustCompile(`"generated_at": "[^"]*"`)
+
+func TestJSONSummaryGolden(t *testing.T) {
+	setTestWindow(t)
+	var b bytes.Buffer
+	if err := writeJSONSummary(&b, testCounts); err != nil {
+		t.Fatal(err)
+	}
+	got := generatedAt.ReplaceAllString(b.String(), `"generated_at": "GENERATED_AT"`)
+	checkGolden(t, "summary.json", got)
+}
+
+// testSenderCounts is three equal senders, whose rounded percents add up to
+// 99.9 rather than 100.
+var testSenderCounts = map[string]int{
+	"alice@example.com": 1,
+	"bob@example.net":   1,
+	"carol@example.org": 1,
+}
+
+func TestSenderTableGolden(t *testing.T) {
+	setTestWindow(t)
+	got := captureStdout(t, func() { printSenderTable(testSenderCounts) })
+	checkGolden(t, "senders_table.txt", got)
+}
+
+func TestSenderJSONGolden(t *testing.T) {
+	setTestWindow(t)
+	var b bytes.Buffer
+	if err := writeSenderJSONSummary(&b, testSenderCounts); err != nil {
+		t.Fatal(err)
+	}
+	got := generatedAt.ReplaceAllString(b.String(), `"generated_at": "GENERATED_AT"`)
+	checkGolden(t, "senders.json", got)
+}
@@ -0,0 +1,28 @@
+package main
+
+import (
+	"fmt"
+	"os"
+	"text/tabwriter"
+	"time"
+)
+
+// pageInfo describes one message list page, for the -debug paging table.
+type pageInfo struct {
+	index   int
+	ids     int
+	hasNext bool
+	elapsed time.Duration
+}
+
+// printPageTable writes the per-page paging details for label to stderr.
+func printPageTable(label string, pages []pageInfo) {
+	finishProgressLine()
+	fmt.Fprintf(os.Stderr, "Message list pages for label %s:\n", label)
+	tw := tabwriter.NewWriter(os.Stderr, 0, 0, 2, ' ', 0)
+	fmt.Fprintln(tw, "Page\tIDs\tNext page\tElapsed")
+	for _, page := range pages {
+		fmt.Fprintf(tw, "%d\t%d\t%t\t%v\n", page.index, page.ids, page.hasNext, page.elapsed.Round(time.Millisecond))
+	}
+	tw.Flush()
+}
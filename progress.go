@@ -0,0 +1,99 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"io"
+	"strings"
+	"sync"
+)
+
+// progressMu guards progressActive, which is set while a "\r"-style progress
+// count is on screen without a trailing newline.
+var progressMu sync.Mutex
+var progressActive bool
+
+// showProgress redraws the in progress count.
+func showProgress(count int) {
+	progressMu.Lock()
+	defer progressMu.Unlock()
+	fmt.Fprintf(status, "\r%d", count)
+	progressActive = true
+}
+
+// accountProgress, under -accounts, holds every account's run so that one
+// progress line shows all of their counts.
+var accountProgress []*accountRun
+
+// showFetchProgress redraws the progress count for a listing. Under -accounts
+// the line shows each account's running total instead, so concurrent
+// accounts share one line rather than overwriting each other's counts.
+func showFetchProgress(ctx context.Context, count int) {
+	run := accountFrom(ctx)
+	if run == nil {
+		showProgress(count)
+		return
+	}
+	run.progress.Add(1)
+
+	progressMu.Lock()
+	defer progressMu.Unlock()
+	var line strings.Builder
+	for i, r := range accountProgress {
+		if i > 0 {
+			line.WriteString("  ")
+		}
+		fmt.Fprintf(&line, "%s %d", r.name, r.progress.Load())
+	}
+	fmt.Fprintf(status, "\r%s", line.String())
+	progressActive = true
+}
+
+// accountStatusf writes a status line, prefixed under -accounts with the
+// account ctx is fetching and started below any progress line on screen.
+func accountStatusf(ctx context.Context, format string, args ...any) {
+	run := accountFrom(ctx)
+	if run == nil {
+		fmt.Fprintf(status, format, args...)
+		return
+	}
+
+	progressMu.Lock()
+	defer progressMu.Unlock()
+	if progressActive {
+		fmt.Fprintln(status)
+		progressActive = false
+	}
+	fmt.Fprintf(status, "%s: %s", run.name, fmt.Sprintf(format, args...))
+}
+
+// clearProgress returns the cursor to the start of the line so the next
+// output overwrites the count.
+func clearProgress() {
+	progressMu.Lock()
+	defer progressMu.Unlock()
+	fmt.Fprint(status, "\r")
+	progressActive = false
+}
+
+// finishProgressLine ends a progress line still on screen with a newline, so
+// that following output starts on a clean line.
+func finishProgressLine() {
+	progressMu.Lock()
+	defer progressMu.Unlock()
+	if progressActive {
+		fmt.Fprintln(status)
+		progressActive = false
+	}
+}
+
+// progressSafeWriter finishes any progress line before each write. It wraps
+// the log output so warnings and fatal errors never land mid-line.
+type progressSafeWriter struct {
+	w io.Writer
+}
+
+func (p progressSafeWriter) Write(b []byte) (int, error) {
+	finishProgressLine()
+	return p.w.Write(b)
+}
@@ -0,0 +1,125 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"strings"
+	"time"
+)
+
+var customQuery = flag.String("query", "", "extra Gmail search terms added to the query, e.g. 'from:example.com has:attachment'")
+var searchAll = flag.Bool("search-all", false, "search all mail (in:anywhere) with -query instead of counting by label, for custom spam-detection queries")
+
+// allMailLabel stands in for the label list under -search-all, where the
+// listing is not restricted to any label.
+const allMailLabel = ""
+
+// labelUnion ORs the -split-labels labels as a search term ({in:spam
+// label:x}) when they are listed in a single pass, and includeSpamTrash
+// lets that listing see the SPAM and TRASH labels when they are requested.
+var labelUnion string
+var includeSpamTrash bool
+
+// validateSearchAll checks the -search-all and -query combination.
+func validateSearchAll() error {
+	if !*searchAll {
+		return nil
+	}
+	if strings.TrimSpace(*customQuery) == "" {
+		return fmt.Errorf("-search-all requires -query")
+	}
+	if flagWasSet("label") || *splitLabels {
+		return fmt.Errorf("-search-all cannot be combined with -label or -split-labels")
+	}
+	if *deleteSpam {
+		// A custom query over all mail can match the inbox as easily as spam
+		return fmt.Errorf("-search-all cannot be combined with -delete")
+	}
+	return nil
+}
+
+// queryFilters returns the optional Gmail search operators selected on the
+// command line.
+func queryFilters() []string {
+	var filters []string
+	if *starred {
+		filters = append(filters, "is:starred")
+	}
+	if *important {
+		filters = append(filters, "is:important")
+	}
+	if *hasAttachment {
+		filters = append(filters, "has:attachment")
+	}
+	if *emailsOnly {
+		// Negated operators AND with the rest of the query like any other term
+		filters = append(filters, "-in:chats")
+	}
+	if *searchAll {
+		filters = append(filters, "in:anywhere")
+	}
+	if q := strings.TrimSpace(*customQuery); q != "" {
+		filters = append(filters, q)
+	}
+	if labelUnion != "" {
+		filters = append(filters, labelUnion)
+	}
+	return filters
+}
+
+// buildQuery returns the Gmail search query used by listSpamMessages. Labels
+// are applied separately through the LabelIds request parameter.
+//
+// Gmail's after:/before: are date-granular and use the account's timezone,
+// which need not match the -timezone used for bucketing, so near midnight the
+// boundary days could be cut short. The query therefore reaches one day past
+// each end of the window and keepMessage drops messages whose internalDate
+// falls outside it.
+func buildQuery() string {
+	var terms []string
+	if cutoffDate != "" {
+		terms = append(terms, "after:"+shiftDate(cutoffDate, -1))
+	}
+	if endDate != "" {
+		terms = append(terms, "before:"+shiftDate(endDate, 1))
+	}
+	terms = append(terms, queryFilters()...)
+	return strings.Join(terms, " ")
+}
+
+// shiftDate moves a YYYY-MM-DD date by the given number of days.
+func shiftDate(date string, days int) string {
+	t, err := time.Parse("2006-01-02", date)
+	if err != nil {
+		return date
+	}
+	return t.AddDate(0, 0, days).Format("2006-01-02")
+}
+
+// inWindow reports whether a YYYY-MM-DD bucket lies within the query window.
+func inWindow(date string) bool {
+	return date >= cutoffDate && (endDate == "" || date < endDate)
+}
+
+// beforeCutoffTime reports whether a message predates the exact start of a
+// -within window, which may fall partway through cutoffDate.
+func beforeCutoffTime(internalDateMs int64) bool {
+	return !cutoffTime.IsZero() && internalDateMs < cutoffTime.UnixMilli()
+}
+
+// describeQuery formats the query sent for a single label.
+func describeQuery(label string) string {
+	if label == allMailLabel {
+		return fmt.Sprintf("%s (all mail)", buildQuery())
+	}
+	return fmt.Sprintf("%s (label %s)", buildQuery(), label)
+}
+
+// filterSummary describes the active query filters for report headers.
+func filterSummary() string {
+	filters := queryFilters()
+	if len(filters) == 0 {
+		return ""
+	}
+	return fmt.Sprintf(" [%s]", strings.Join(filters, " "))
+}
@@ -0,0 +1,65 @@
+package main
+
+import (
+	"context"
+	"flag"
+	"sync"
+	"time"
+)
+
+var rps = flag.Float64("rps", 0, "maximum Gmail API requests per second across all list, get and trash calls (0 = unlimited)")
+
+// requestLimiter spaces every Gmail request at least 1/-rps apart, or is nil
+// when -rps is unlimited.
+var requestLimiter *rateLimiter
+
+// rateLimiter hands out evenly spaced request slots. Unlike -workers or
+// -adaptive, which cap how many requests are in flight, it caps how fast they
+// start, so bursts are smoothed out before Gmail has to push back.
+type rateLimiter struct {
+	mu       sync.Mutex
+	interval time.Duration
+	next     time.Time
+}
+
+func newRateLimiter(perSecond float64) *rateLimiter {
+	return &rateLimiter{interval: time.Duration(float64(time.Second) / perSecond)}
+}
+
+// wait blocks until the caller's slot, recording any delay in stats.
+func (l *rateLimiter) wait(ctx context.Context) error {
+	l.mu.Lock()
+	now := time.Now()
+	slot := l.next
+	if slot.Before(now) {
+		slot = now
+	}
+	l.next = slot.Add(l.interval)
+	l.mu.Unlock()
+
+	delay := slot.Sub(now)
+	if delay <= 0 {
+		return nil
+	}
+	countStats(ctx, func(s *runStats) {
+		s.throttled.Add(1)
+		s.throttleWait.Add(int64(delay))
+	})
+
+	timer := time.NewTimer(delay)
+	defer timer.Stop()
+	select {
+	case <-timer.C:
+		return nil
+	case <-ctx.Done():
+		return ctx.Err()
+	}
+}
+
+// awaitRequestSlot waits for the -rps limiter, if any, before a request.
+func awaitRequestSlot(ctx context.Context) error {
+	if requestLimiter == nil {
+		return nil
+	}
+	return requestLimiter.wait(ctx)
+}
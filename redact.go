@@ -0,0 +1,70 @@
+package main
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"sync"
+)
+
+var redact = flag.Bool("redact", false, "replace Gmail message and thread IDs with stable pseudonyms (msg-0001) in -dump-raw files and debug logs, for shareable bug reports")
+
+// pseudonyms numbers each Gmail ID the first time it is redacted. Message
+// and thread IDs share the numbering, so a thread started by a message keeps
+// that message's number (msg-0003, thread-0003).
+var pseudonyms = struct {
+	sync.Mutex
+	ids map[string]int
+}{ids: make(map[string]int)}
+
+// pseudonym returns id's stable stand-in with the given prefix.
+func pseudonym(prefix, id string) string {
+	pseudonyms.Lock()
+	defer pseudonyms.Unlock()
+	n, ok := pseudonyms.ids[id]
+	if !ok {
+		n = len(pseudonyms.ids) + 1
+		pseudonyms.ids[id] = n
+	}
+	return fmt.Sprintf("%s-%04d", prefix, n)
+}
+
+// redactID returns a message ID as it should appear in debug output.
+func redactID(id string) string {
+	if !*redact || id == "" {
+		return id
+	}
+	return pseudonym("msg", id)
+}
+
+// redactJSON rewrites every "id" and "threadId" value in an encoded API
+// response with its pseudonym.
+func redactJSON(b []byte) ([]byte, error) {
+	var v any
+	if err := json.Unmarshal(b, &v); err != nil {
+		return nil, err
+	}
+	redactValue(v)
+	return json.MarshalIndent(v, "", "  ")
+}
+
+func redactValue(v any) {
+	switch v := v.(type) {
+	case map[string]any:
+		for key, value := range v {
+			id, isString := value.(string)
+			switch {
+			case key == "id" && isString:
+				v[key] = redactID(id)
+			case key == "threadId" && isString && id != "":
+				v[key] = pseudonym("thread", id)
+			default:
+				redactValue(value)
+			}
+		}
+	case []any:
+		for _, value := range v {
+			redactValue(value)
+		}
+	}
+}
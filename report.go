@@ -0,0 +1,126 @@
+package main
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"time"
+)
+
+var csvOut = flag.String("csv-out", "", "also write the daily counts as csv to this file")
+var jsonOut = flag.String("json-out", "", "also write the daily counts as json to this file")
+
+// jsonSchemaVersion is the jsonReport schema_version. It is bumped whenever
+// a field is removed, renamed or changes meaning; new fields may be added
+// without a bump, so consumers should ignore fields they don't know.
+const jsonSchemaVersion = 1
+
+// jsonReport is the -format json representation of the daily summary, also
+// used by -json-out, -webhook and -baseline. Version 1:
+//   - schema_version: jsonSchemaVersion
+//   - generated_at: RFC 3339 time the report was written, in UTC
+//   - window, timezone: the query window and bucketing timezone
+//   - counts: YYYY-MM-DD date -> messages, days without spam omitted
+//   - weeks: with -granularity week, ISO week entries (see weekEntry)
+//   - total, avg_per_day, undated: as in the text summary
+//   - longest_spam_free_streak: {days, start, end}
+//   - stats: with -percentiles, {mean, p50, p90, p99}
+//   - no_auth_placeholder: true when -tolerate-missing-creds skipped
+//     authentication and nothing was counted
+type jsonReport struct {
+	SchemaVersion int            `json:"schema_version"`
+	GeneratedAt   string         `json:"generated_at"`
+	Window        string         `json:"window"`
+	Timezone      string         `json:"timezone"`
+	Counts        map[string]int `json:"counts"`
+	Weeks         []weekEntry    `json:"weeks,omitempty"`
+	Total         int            `json:"total"`
+	AvgPerDay     float64        `json:"avg_per_day"`
+	Undated       int64          `json:"undated"`
+	Streak        spamFreeStreak `json:"longest_spam_free_streak"`
+	Stats         *dailyStats    `json:"stats,omitempty"`
+
+	NoAuthPlaceholder bool `json:"no_auth_placeholder,omitempty"`
+}
+
+// averagePerDay divides total by the length of the query window, not by the
+// number of days that had spam, so quiet days pull the average down.
+func averagePerDay(total int) float64 {
+	if windowDays <= 0 {
+		return float64(total)
+	}
+	return float64(total) / float64(windowDays)
+}
+
+// writeJSONSummary writes the daily counts as an indented JSON object.
+func writeJSONSummary(w io.Writer, spamCounts map[string]int) error {
+	enc := json.NewEncoder(w)
+	enc.SetIndent("", "  ")
+	return enc.Encode(newJSONReport(spamCounts))
+}
+
+// writeJSONLineSummary writes the same report as writeJSONSummary on a single
+// newline-terminated line, for -format jsonl and line-based log shippers.
+func writeJSONLineSummary(w io.Writer, spamCounts map[string]int) error {
+	return json.NewEncoder(w).Encode(newJSONReport(spamCounts))
+}
+
+func newJSONReport(spamCounts map[string]int) jsonReport {
+	total := 0
+	for _, count := range spamCounts {
+		total += count
+	}
+
+	report := jsonReport{
+		SchemaVersion: jsonSchemaVersion,
+		GeneratedAt:   time.Now().UTC().Format(time.RFC3339),
+		Window:        windowDescription,
+		Timezone:      location.String(),
+		Counts:        spamCounts,
+		Total:         total,
+		AvgPerDay:     roundTo2(averagePerDay(total)),
+		Undated:       undatedCount.Load(),
+		Streak:        longestSpamFreeStreak(spamCounts),
+
+		NoAuthPlaceholder: noAuthPlaceholder,
+	}
+	if *granularity == "week" {
+		report.Weeks = weeklyCounts(spamCounts)
+	}
+	if *showPercentiles {
+		stats := computeDailyStats(spamCounts)
+		report.Stats = &stats
+	}
+	return report
+}
+
+// writeFileOutputs renders the daily counts to each file requested with
+// -csv-out and -json-out, in addition to the -format output on stdout.
+func writeFileOutputs(spamCounts map[string]int) error {
+	if *csvOut != "" {
+		if err := writeToFile(*csvOut, func(w io.Writer) error { return writeCSVSummary(w, spamCounts) }); err != nil {
+			return fmt.Errorf("unable to write %s: %v", *csvOut, err)
+		}
+	}
+	if *jsonOut != "" {
+		if err := writeToFile(*jsonOut, func(w io.Writer) error { return writeJSONSummary(w, spamCounts) }); err != nil {
+			return fmt.Errorf("unable to write %s: %v", *jsonOut, err)
+		}
+	}
+	return nil
+}
+
+// writeToFile creates path and renders into it.
+func writeToFile(path string, render func(io.Writer) error) error {
+	f, err := os.Create(path)
+	if err != nil {
+		return err
+	}
+	if err := render(f); err != nil {
+		f.Close()
+		return err
+	}
+	return f.Close()
+}
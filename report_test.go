@@ -0,0 +1,27 @@
+package main
+
+import (
+	"bytes"
+	"encoding/json"
+	"testing"
+)
+
+func TestJSONReportSchemaVersion(t *testing.T) {
+	setTestWindow(t)
+	var b bytes.Buffer
+	if err := writeJSONSummary(&b, testCounts); err != nil {
+		t.Fatal(err)
+	}
+
+	var report map[string]any
+	if err := json.Unmarshal(b.Bytes(), &report); err != nil {
+		t.Fatal(err)
+	}
+	version, ok := report["schema_version"].(float64)
+	if !ok {
+		t.Fatalf("schema_version missing from %s", b.String())
+	}
+	if int(version) != jsonSchemaVersion {
+		t.Errorf("schema_version = %v, want %d", version, jsonSchemaVersion)
+	}
+}
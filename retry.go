@@ -0,0 +1,162 @@
+package main
+
+import (
+	"errors"
+	"fmt"
+	"math"
+	"net/http"
+	"strconv"
+	"strings"
+	"time"
+
+	"github.com/cenkalti/backoff/v5"
+	"google.golang.org/api/googleapi"
+)
+
+// requestTimeoutError reports that a single message fetch exceeded
+// -request-timeout, as opposed to the whole run exceeding -timeout.
+type requestTimeoutError struct {
+	id    string
+	after time.Duration
+}
+
+func (e *requestTimeoutError) Error() string {
+	return fmt.Sprintf("request for message %s timed out after %v", e.id, e.after)
+}
+
+// errorClass says whether a failed Gmail call is worth retrying.
+type errorClass int
+
+const (
+	transientError errorClass = iota
+	permanentError
+)
+
+// rateLimitReasons are the 403 error reasons Gmail uses for quota throttling,
+// which clear up on their own unlike other 403s.
+var rateLimitReasons = map[string]bool{
+	"rateLimitExceeded":     true,
+	"userRateLimitExceeded": true,
+}
+
+// classifyStatus classifies a failed Gmail call by its HTTP status code and
+// error reasons: throttling (429, rate-limit 403s) and server errors (5xx)
+// are transient, any other 4xx is permanent.
+func classifyStatus(code int, reasons []string) errorClass {
+	switch {
+	case code == http.StatusTooManyRequests, code >= 500:
+		return transientError
+	case code == http.StatusForbidden:
+		for _, reason := range reasons {
+			if rateLimitReasons[reason] {
+				return transientError
+			}
+		}
+		return permanentError
+	case code >= 400:
+		return permanentError
+	}
+	return transientError
+}
+
+// retryAfterError is a throttled Gmail error that carried a Retry-After
+// header. It wraps the original error, which is what the user sees if the
+// retries run out.
+type retryAfterError struct {
+	err  error
+	wait time.Duration
+}
+
+func (e *retryAfterError) Error() string {
+	return fmt.Sprintf("%v (Retry-After %v)", e.err, e.wait)
+}
+
+func (e *retryAfterError) Unwrap() error { return e.err }
+
+// retryBackOff is the exponential backoff for Gmail calls, with Retry-After
+// as a floor: after a response carrying the header, the next wait is the
+// longer of the two, so Retry-After: 0 still backs off.
+type retryBackOff struct {
+	*backoff.ExponentialBackOff
+	floor time.Duration
+}
+
+func newRetryBackOff() *retryBackOff {
+	return &retryBackOff{ExponentialBackOff: backoff.NewExponentialBackOff()}
+}
+
+func (b *retryBackOff) NextBackOff() time.Duration {
+	next := b.ExponentialBackOff.NextBackOff()
+	floor := b.floor
+	b.floor = 0
+	if next == backoff.Stop {
+		return next
+	}
+	return max(next, floor)
+}
+
+// classify is classifyError that also records any Retry-After as the floor
+// for the next wait.
+func (b *retryBackOff) classify(err error) error {
+	err = classifyError(err)
+	var retryAfter *retryAfterError
+	if errors.As(err, &retryAfter) {
+		b.floor = retryAfter.wait
+	}
+	return err
+}
+
+// classifyError inspects an error returned by a Gmail call before it is handed
+// back to backoff.Retry. Errors that retrying cannot fix are marked
+// permanent. When Gmail rejects a request with 429 or 503 and includes a
+// Retry-After header, the error is wrapped in a retryAfterError so
+// retryBackOff waits at least that long. Transport errors and per-request
+// timeouts carry no status code and stay transient.
+func classifyError(err error) error {
+	var apiErr *googleapi.Error
+	if !errors.As(err, &apiErr) {
+		return err
+	}
+
+	var reasons []string
+	for _, item := range apiErr.Errors {
+		reasons = append(reasons, item.Reason)
+	}
+	if classifyStatus(apiErr.Code, reasons) == permanentError {
+		return backoff.Permanent(err)
+	}
+	if apiErr.Code != http.StatusTooManyRequests && apiErr.Code != http.StatusServiceUnavailable {
+		return err
+	}
+
+	if seconds, ok := parseRetryAfter(apiErr.Header.Get("Retry-After"), time.Now()); ok {
+		return &retryAfterError{err: err, wait: time.Duration(seconds) * time.Second}
+	}
+	return err
+}
+
+// parseRetryAfter parses a Retry-After header value, which is either a number
+// of seconds or an HTTP date, into a whole number of seconds to wait.
+func parseRetryAfter(value string, now time.Time) (int, bool) {
+	value = strings.TrimSpace(value)
+	if value == "" {
+		return 0, false
+	}
+
+	if seconds, err := strconv.Atoi(value); err == nil {
+		if seconds < 0 {
+			return 0, false
+		}
+		return seconds, true
+	}
+
+	if when, err := http.ParseTime(value); err == nil {
+		wait := when.Sub(now)
+		if wait <= 0 {
+			return 0, true
+		}
+		return int(math.Ceil(wait.Seconds())), true
+	}
+
+	return 0, false
+}
@@ -0,0 +1,138 @@
+package main
+
+import (
+	"errors"
+	"net/http"
+	"strings"
+	"testing"
+	"time"
+
+	"github.com/cenkalti/backoff/v5"
+	"google.golang.org/api/googleapi"
+)
+
+func TestParseRetryAfter(t *testing.T) {
+	// Half a second past the minute, so HTTP dates exercise the rounding up
+	now := time.Date(2024, 3, 1, 12, 0, 0, 500_000_000, time.UTC)
+	tests := []struct {
+		name  string
+		value string
+		want  int
+		ok    bool
+	}{
+		{"delta seconds", "120", 120, true},
+		{"delta seconds with spaces", " 7 ", 7, true},
+		{"zero seconds", "0", 0, true},
+		{"negative seconds", "-5", 0, false},
+		{"http date", "Fri, 01 Mar 2024 12:01:30 GMT", 90, true},
+		{"http date in the past", "Fri, 01 Mar 2024 11:59:00 GMT", 0, true},
+		{"empty", "", 0, false},
+		{"invalid", "soon", 0, false},
+		{"invalid date", "Friday at noon", 0, false},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			got, ok := parseRetryAfter(tt.value, now)
+			if got != tt.want || ok != tt.ok {
+				t.Errorf("parseRetryAfter(%q) = %d, %t; want %d, %t", tt.value, got, ok, tt.want, tt.ok)
+			}
+		})
+	}
+}
+
+func TestClassifyErrorRetryAfter(t *testing.T) {
+	header := http.Header{}
+	header.Set("Retry-After", "30")
+	apiErr := &googleapi.Error{Code: http.StatusTooManyRequests, Message: "Too many concurrent requests for user", Header: header}
+	err := classifyError(apiErr)
+
+	var retryAfter *retryAfterError
+	if !errors.As(err, &retryAfter) {
+		t.Fatalf("classifyError returned %v (%T), want a retryAfterError", err, err)
+	}
+	if retryAfter.wait != 30*time.Second {
+		t.Errorf("Retry-After wait = %v, want 30s", retryAfter.wait)
+	}
+	// The Gmail error must survive for the message shown when retries run out
+	var got *googleapi.Error
+	if !errors.As(err, &got) || got != apiErr {
+		t.Errorf("classifyError = %v, want it to wrap the Gmail error", err)
+	}
+	if !strings.Contains(err.Error(), "Too many concurrent requests") {
+		t.Errorf("error message %q lost the Gmail error", err)
+	}
+}
+
+func TestRetryBackOffRetryAfterFloor(t *testing.T) {
+	tests := []struct {
+		retryAfter string
+		atLeast    time.Duration
+	}{
+		// Retry-After: 0 must not mean an immediate retry
+		{"0", time.Millisecond},
+		{"30", 30 * time.Second},
+	}
+	for _, tt := range tests {
+		header := http.Header{}
+		header.Set("Retry-After", tt.retryAfter)
+		b := newRetryBackOff()
+		err := b.classify(&googleapi.Error{Code: http.StatusServiceUnavailable, Header: header})
+		if err == nil {
+			t.Fatal("classify returned nil")
+		}
+		if next := b.NextBackOff(); next < tt.atLeast {
+			t.Errorf("Retry-After %s: next wait %v, want at least %v", tt.retryAfter, next, tt.atLeast)
+		}
+		// The floor applies to the next wait only
+		if next := b.NextBackOff(); next >= 30*time.Second {
+			t.Errorf("Retry-After %s: second wait %v, want the plain exponential interval", tt.retryAfter, next)
+		}
+	}
+}
+
+func TestClassifyErrorInvalidRetryAfter(t *testing.T) {
+	header := http.Header{}
+	header.Set("Retry-After", "later")
+	apiErr := &googleapi.Error{Code: http.StatusServiceUnavailable, Header: header}
+	err := classifyError(apiErr)
+
+	var retryAfter *retryAfterError
+	if errors.As(err, &retryAfter) {
+		t.Errorf("classifyError with an invalid Retry-After returned a retryAfterError: %v", err)
+	}
+	var permanent *backoff.PermanentError
+	if errors.As(err, &permanent) {
+		t.Errorf("classifyError of a 503 returned a permanent error: %v", err)
+	}
+	if err != error(apiErr) {
+		t.Errorf("classifyError = %v, want the original error", err)
+	}
+}
+
+func TestClassifyStatus(t *testing.T) {
+	tests := []struct {
+		name    string
+		code    int
+		reasons []string
+		want    errorClass
+	}{
+		{"too many requests", http.StatusTooManyRequests, nil, transientError},
+		{"internal server error", http.StatusInternalServerError, []string{"backendError"}, transientError},
+		{"bad gateway", http.StatusBadGateway, nil, transientError},
+		{"service unavailable", http.StatusServiceUnavailable, nil, transientError},
+		{"403 rate limit", http.StatusForbidden, []string{"rateLimitExceeded"}, transientError},
+		{"403 user rate limit", http.StatusForbidden, []string{"userRateLimitExceeded"}, transientError},
+		{"403 rate limit among other reasons", http.StatusForbidden, []string{"forbidden", "rateLimitExceeded"}, transientError},
+		{"403 insufficient permissions", http.StatusForbidden, []string{"insufficientPermissions"}, permanentError},
+		{"403 without reasons", http.StatusForbidden, nil, permanentError},
+		{"not found", http.StatusNotFound, []string{"notFound"}, permanentError},
+		{"bad request", http.StatusBadRequest, nil, permanentError},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			if got := classifyStatus(tt.code, tt.reasons); got != tt.want {
+				t.Errorf("classifyStatus(%d, %v) = %v, want %v", tt.code, tt.reasons, got, tt.want)
+			}
+		})
+	}
+}
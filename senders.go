@@ -0,0 +1,274 @@
+package main
+
+import (
+	"context"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"fmt"
+	"io"
+	"math"
+	"net/mail"
+	"sort"
+	"strconv"
+	"strings"
+	"time"
+
+	"google.golang.org/api/gmail/v1"
+)
+
+// messageHeader returns the value of the named header, or "" if the message
+// was not fetched with it.
+func messageHeader(m *gmail.Message, name string) string {
+	if m.Payload == nil {
+		return ""
+	}
+	for _, h := range m.Payload.Headers {
+		if strings.EqualFold(h.Name, name) {
+			return h.Value
+		}
+	}
+	return ""
+}
+
+// messageSender returns the lower-cased address from the From header, falling
+// back to the raw header value when it cannot be parsed.
+func messageSender(m *gmail.Message) string {
+	from := strings.TrimSpace(messageHeader(m, "From"))
+	if from == "" {
+		return "(unknown)"
+	}
+	if addr, err := mail.ParseAddress(from); err == nil {
+		return strings.ToLower(addr.Address)
+	}
+	return strings.ToLower(from)
+}
+
+// senderDiversity holds the number of messages and the distinct senders seen on one day.
+type senderDiversity struct {
+	messages int
+	senders  map[string]bool
+}
+
+func getSenderDiversity(ctx context.Context, srv *gmail.Service) (map[string]*senderDiversity, error) {
+	diversity := make(map[string]*senderDiversity)
+
+	messages, err := collectMessages(ctx, srv)
+	if err != nil {
+		return nil, err
+	}
+
+	for _, m := range messages {
+		emailDate, ok := messageDate(m)
+		if !ok {
+			continue
+		}
+		day := diversity[emailDate]
+		if day == nil {
+			day = &senderDiversity{senders: make(map[string]bool)}
+			diversity[emailDate] = day
+		}
+		day.messages++
+		day.senders[messageSender(m)] = true
+	}
+
+	return diversity, nil
+}
+
+func printSenderDiversity(diversity map[string]*senderDiversity) {
+	var dates []string
+	for date := range diversity {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	total := 0
+	for _, date := range dates {
+		day := diversity[date]
+		total += day.messages
+		fmt.Printf("%s: %d msgs from %d senders\n", date, day.messages, len(day.senders))
+	}
+	fmt.Printf("Total: %d\n", total)
+}
+
+// shortHash returns the first 8 hex characters of the SHA-256 of value, which
+// is stable across runs so relative volumes can be compared without
+// revealing who sent the mail.
+func shortHash(value string) string {
+	sum := sha256.Sum256([]byte(value))
+	return hex.EncodeToString(sum[:])[:8]
+}
+
+// senderDomain returns the domain part of a sender address.
+func senderDomain(sender string) string {
+	if i := strings.LastIndex(sender, "@"); i >= 0 {
+		return sender[i+1:]
+	}
+	return sender
+}
+
+// senderKey returns the key a message is counted under for -by-sender,
+// -by-domain or -domain-tiers, anonymized when -hash-senders is set.
+func senderKey(m *gmail.Message) string {
+	sender := messageSender(m)
+	if *byDomain || *domainTiers {
+		if *hashSenders {
+			return shortHash(senderDomain(sender))
+		}
+		return senderDomain(sender)
+	}
+
+	if !*hashSenders {
+		return sender
+	}
+	if *hashKeepDomain {
+		if i := strings.LastIndex(sender, "@"); i >= 0 {
+			return shortHash(sender[:i]) + sender[i:]
+		}
+	}
+	return shortHash(sender)
+}
+
+// senderColumn names what -by-sender/-by-domain output is keyed on.
+func senderColumn() string {
+	if *byDomain {
+		return "domain"
+	}
+	return "sender"
+}
+
+func getSenderCounts(ctx context.Context, srv *gmail.Service) (map[string]int, error) {
+	senderCounts := make(map[string]int)
+
+	messages, err := collectMessages(ctx, srv)
+	if err != nil {
+		return nil, err
+	}
+
+	for _, m := range messages {
+		senderCounts[senderKey(m)]++
+	}
+
+	return senderCounts, nil
+}
+
+// sortedSenders returns the senders ordered by descending count, then by name.
+func sortedSenders(senderCounts map[string]int) []string {
+	var senders []string
+	for sender := range senderCounts {
+		senders = append(senders, sender)
+	}
+	sort.Slice(senders, func(i, j int) bool {
+		if senderCounts[senders[i]] != senderCounts[senders[j]] {
+			return senderCounts[senders[i]] > senderCounts[senders[j]]
+		}
+		return senders[i] < senders[j]
+	})
+	return senders
+}
+
+func printSenderSummary(senderCounts map[string]int) {
+	total := countsTotal(senderCounts)
+	for _, sender := range sortedSenders(senderCounts) {
+		count := senderCounts[sender]
+		fmt.Printf("%6d %5.1f%% %s\n", count, percentOf(count, total), sender)
+	}
+	fmt.Printf("Total: %d\n", total)
+}
+
+func countsTotal(counts map[string]int) int {
+	total := 0
+	for _, count := range counts {
+		total += count
+	}
+	return total
+}
+
+// percentOf returns count as a percentage of total, rounded to one decimal.
+// Each row is rounded on its own, so a column can sum to a few tenths off
+// 100%.
+func percentOf(count, total int) float64 {
+	if total == 0 {
+		return 0
+	}
+	return math.Round(1000*float64(count)/float64(total)) / 10
+}
+
+// writeSenderCSVSummary writes the sender or domain counts as csv with a
+// sender,count (or domain,count) header row.
+func writeSenderCSVSummary(w io.Writer, senderCounts map[string]int) error {
+	cw, err := newCSVWriter(w)
+	if err != nil {
+		return err
+	}
+
+	if err := cw.Write([]string{senderColumn(), "count", "percent"}); err != nil {
+		return err
+	}
+	total := countsTotal(senderCounts)
+	for _, sender := range sortedSenders(senderCounts) {
+		count := senderCounts[sender]
+		percent := strconv.FormatFloat(percentOf(count, total), 'f', 1, 64)
+		if err := cw.Write([]string{sender, strconv.Itoa(count), percent}); err != nil {
+			return err
+		}
+	}
+	cw.Flush()
+	return cw.Error()
+}
+
+// senderReport is the -format json representation of -by-sender and
+// -by-domain. schema_version, generated_at, window and timezone are as in
+// jsonReport; then:
+//   - by: "sender" or "domain", what each entry's name is
+//   - senders: {name, count, percent} entries by descending count, percent
+//     rounded to one decimal as in the text output, so they sum to ~100
+//   - total: messages counted
+type senderReport struct {
+	SchemaVersion int           `json:"schema_version"`
+	GeneratedAt   string        `json:"generated_at"`
+	Window        string        `json:"window"`
+	Timezone      string        `json:"timezone"`
+	By            string        `json:"by"`
+	Senders       []senderEntry `json:"senders"`
+	Total         int           `json:"total"`
+}
+
+// senderEntry is one sender or domain in a senderReport.
+type senderEntry struct {
+	Name    string  `json:"name"`
+	Count   int     `json:"count"`
+	Percent float64 `json:"percent"`
+}
+
+func newSenderReport(senderCounts map[string]int) senderReport {
+	total := countsTotal(senderCounts)
+	report := senderReport{
+		SchemaVersion: jsonSchemaVersion,
+		GeneratedAt:   time.Now().UTC().Format(time.RFC3339),
+		Window:        windowDescription,
+		Timezone:      location.String(),
+		By:            senderColumn(),
+		Senders:       []senderEntry{},
+		Total:         total,
+	}
+	for _, sender := range sortedSenders(senderCounts) {
+		count := senderCounts[sender]
+		report.Senders = append(report.Senders, senderEntry{Name: sender, Count: count, Percent: percentOf(count, total)})
+	}
+	return report
+}
+
+// writeSenderJSONSummary writes the sender or domain counts as an indented
+// JSON object.
+func writeSenderJSONSummary(w io.Writer, senderCounts map[string]int) error {
+	enc := json.NewEncoder(w)
+	enc.SetIndent("", "  ")
+	return enc.Encode(newSenderReport(senderCounts))
+}
+
+// writeSenderJSONLineSummary writes the same report as writeSenderJSONSummary
+// on a single line, for -format jsonl.
+func writeSenderJSONLineSummary(w io.Writer, senderCounts map[string]int) error {
+	return json.NewEncoder(w).Encode(newSenderReport(senderCounts))
+}
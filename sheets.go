@@ -0,0 +1,77 @@
+package main
+
+import (
+	"context"
+	"errors"
+	"flag"
+	"fmt"
+	"net/http"
+	"sort"
+	"strings"
+	"time"
+
+	"github.com/cenkalti/backoff/v5"
+	"google.golang.org/api/googleapi"
+	"google.golang.org/api/sheets/v4"
+)
+
+var sheetID = flag.String("sheet-id", "", "append the daily counts to this Google Sheet (requests the spreadsheets scope)")
+var sheetTab = flag.String("sheet-tab", "Sheet1", "tab of -sheet-id to append rows to")
+
+// sheetsService is set when -sheet-id is given.
+var sheetsService *sheets.Service
+
+// appendToSheet appends one row per day to the -sheet-tab tab of -sheet-id:
+// the time of this run, the date and its count. The run time keeps rows
+// from successive runs over overlapping windows distinguishable.
+func appendToSheet(ctx context.Context, spamCounts map[string]int) error {
+	spreadsheet, err := sheetsService.Spreadsheets.Get(*sheetID).Fields("sheets.properties.title").Context(ctx).Do()
+	if err != nil {
+		var apiErr *googleapi.Error
+		if errors.As(err, &apiErr) && (apiErr.Code == http.StatusNotFound || apiErr.Code == http.StatusForbidden) {
+			return fmt.Errorf("spreadsheet %s does not exist or is not shared with the signed-in account: %w", *sheetID, err)
+		}
+		return fmt.Errorf("unable to open spreadsheet %s: %v", *sheetID, err)
+	}
+
+	var tabs []string
+	found := false
+	for _, sheet := range spreadsheet.Sheets {
+		tabs = append(tabs, sheet.Properties.Title)
+		found = found || sheet.Properties.Title == *sheetTab
+	}
+	if !found {
+		return fmt.Errorf("spreadsheet %s has no tab %q (available: %s)", *sheetID, *sheetTab, strings.Join(tabs, ", "))
+	}
+
+	var dates []string
+	for date := range spamCounts {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	capturedAt := time.Now().In(location).Format(time.RFC3339)
+	values := &sheets.ValueRange{}
+	for _, date := range dates {
+		values.Values = append(values.Values, []interface{}{capturedAt, date, spamCounts[date]})
+	}
+	if len(values.Values) == 0 {
+		return nil
+	}
+
+	retry := newRetryBackOff()
+	_, err = backoff.Retry(ctx, func() (*sheets.AppendValuesResponse, error) {
+		// Quote the tab name so names with spaces or punctuation work in A1 notation
+		appendRange := "'" + strings.ReplaceAll(*sheetTab, "'", "''") + "'!A:C"
+		r, err := sheetsService.Spreadsheets.Values.Append(*sheetID, appendRange, values).
+			ValueInputOption("USER_ENTERED").Context(ctx).Do()
+		if err != nil {
+			return r, retry.classify(err)
+		}
+		return r, nil
+	}, backoff.WithBackOff(retry))
+	if err != nil {
+		return fmt.Errorf("unable to append to spreadsheet %s: %v", *sheetID, err)
+	}
+	return nil
+}
@@ -0,0 +1,115 @@
+package main
+
+import (
+	"context"
+	"flag"
+	"fmt"
+	"os"
+	"strings"
+	"sync/atomic"
+	"time"
+)
+
+var showStats = flag.Bool("stats", false, "print request counts (pages, messages, retries, failures) and elapsed time to stderr at exit")
+
+// runStart is when the process started, for the -stats elapsed time.
+var runStart = time.Now()
+
+// runStats counts Gmail requests, for the whole run in stats and for each
+// account under -accounts.
+type runStats struct {
+	pages    atomic.Int64 // message list pages walked
+	listed   atomic.Int64 // message IDs returned by the list pages
+	fetched  atomic.Int64 // messages fetched successfully
+	retries  atomic.Int64 // attempts beyond the first for any request
+	failures atomic.Int64 // message fetches that failed after all retries
+	quota    atomic.Int64 // estimated Gmail quota units, see the quota* costs
+
+	duplicates atomic.Int64 // listed IDs skipped as already seen on another page or label
+	filtered   atomic.Int64 // fetched messages rejected by keepMessage
+	estimated  atomic.Int64 // sum of resultSizeEstimate from each listing's first page
+
+	throttled    atomic.Int64 // requests that -rps made wait
+	throttleWait atomic.Int64 // total -rps wait, as a time.Duration
+}
+
+// stats counts Gmail requests over the whole run.
+var stats runStats
+
+// countStats applies add to the run's stats and, under -accounts, to the stats
+// of the account ctx is fetching.
+func countStats(ctx context.Context, add func(*runStats)) {
+	add(&stats)
+	if run := accountFrom(ctx); run != nil {
+		add(&run.stats)
+	}
+}
+
+// Gmail API quota units per call, from the Gmail usage limits documentation.
+// Every attempt is charged, since retried and failed calls still count.
+const (
+	quotaMessagesList  = 5
+	quotaMessagesGet   = 5
+	quotaMessagesTrash = 5
+	quotaLabelsList    = 1
+	quotaLabelsGet     = 1
+	quotaGetProfile    = 1
+)
+
+// printStats writes the -stats line to stderr.
+func printStats() {
+	printRunStats("", &stats)
+}
+
+// printRunStats writes the -stats lines for s, each starting with prefix.
+func printRunStats(prefix string, s *runStats) {
+	fmt.Fprintf(os.Stderr, "%sListed %d messages across %d pages, fetched %d in %.1fs; %d retries; %d failures.\n",
+		prefix, s.listed.Load(), s.pages.Load(), s.fetched.Load(),
+		time.Since(runStart).Seconds(), s.retries.Load(), s.failures.Load())
+	fmt.Fprintf(os.Stderr, "%sEstimated quota units used: %d\n", prefix, s.quota.Load())
+	if n := s.throttled.Load(); n > 0 {
+		fmt.Fprintf(os.Stderr, "%s-rps delayed %d requests by %v in total\n", prefix, n, time.Duration(s.throttleWait.Load()).Round(time.Millisecond))
+	}
+}
+
+// printReconciliation explains, when they differ, how the number of listed
+// messages became the number counted, e.g. "Listed 500, fetched 498,
+// counted 495 (3 with invalid or missing dates, 2 fetch failures)".
+func printReconciliation(spamCounts map[string]int) {
+	if *checkpointPath != "" {
+		// A resumed -checkpoint holds counts from earlier runs
+		return
+	}
+	reconcile("", &stats, countsTotal(spamCounts), undatedCount.Load(), droppedMessages.Load())
+}
+
+// reconcile prints the reconciliation line for s when counted differs from
+// the number listed. prefix names the account under -accounts.
+func reconcile(prefix string, s *runStats, counted int, undated, dropped int64) {
+	listed := s.listed.Load()
+	if listed == 0 || int64(counted) == listed {
+		// Nothing was listed this run (IMAP, -merge), or nothing went missing
+		return
+	}
+
+	var reasons []string
+	for _, reason := range []struct {
+		n    int64
+		what string
+	}{
+		{undated, "with invalid or missing dates"},
+		{s.failures.Load(), "fetch failures"},
+		{s.duplicates.Load(), "duplicates"},
+		{s.filtered.Load(), "filtered out"},
+		{dropped, "dropped on cancellation"},
+	} {
+		if reason.n > 0 {
+			reasons = append(reasons, fmt.Sprintf("%d %s", reason.n, reason.what))
+		}
+	}
+	detail := ""
+	if len(reasons) > 0 {
+		detail = " (" + strings.Join(reasons, ", ") + ")"
+	}
+	fmt.Fprintf(status, "%sListed %d, fetched %d, counted %d%s.\n", prefix, listed, s.fetched.Load(), counted, detail)
+}
@@ -0,0 +1,72 @@
+package main
+
+import (
+	"encoding/json"
+	"errors"
+	"flag"
+	"fmt"
+	"log"
+	"os"
+	"time"
+)
+
+var statusFile = flag.String("status-file", "", "write the run status as JSON (schema_version, success, total, error, duration_ms) to this file on exit, for supervisors that can't capture stdout")
+
+// reportedTotal is the number of messages the report counted, for
+// -status-file. It stays 0 for -split-labels and -sender-diversity, whose
+// counts overlap.
+var reportedTotal int
+
+// runStatus is the -status-file record. It shares jsonSchemaVersion with the
+// JSON report, so both are versioned together.
+type runStatus struct {
+	SchemaVersion int    `json:"schema_version"`
+	Success       bool   `json:"success"`
+	Total         int    `json:"total"`
+	Error         string `json:"error,omitempty"`
+	DurationMs    int64  `json:"duration_ms"`
+}
+
+// noteTotal records the sum of counts as the run's reportedTotal.
+func noteTotal(counts map[string]int) {
+	reportedTotal = countsTotal(counts)
+}
+
+// writeStatusFile records the outcome of the run at -status-file, renaming a
+// temporary file into place so a polling supervisor never reads half of it.
+// A nil runErr means success.
+func writeStatusFile(runErr error) {
+	if *statusFile == "" {
+		return
+	}
+	s := runStatus{
+		SchemaVersion: jsonSchemaVersion,
+		Success:       runErr == nil,
+		Total:         reportedTotal,
+		DurationMs:    time.Since(runStart).Milliseconds(),
+	}
+	if runErr != nil {
+		s.Error = runErr.Error()
+	}
+	b, err := json.Marshal(s)
+	if err != nil {
+		log.Printf("Unable to write -status-file: %v", err)
+		return
+	}
+	tmp := *statusFile + ".tmp"
+	if err := os.WriteFile(tmp, append(b, '\n'), 0600); err != nil {
+		log.Printf("Unable to write -status-file: %v", err)
+		return
+	}
+	if err := os.Rename(tmp, *statusFile); err != nil {
+		log.Printf("Unable to write -status-file: %v", err)
+	}
+}
+
+// fatalf is log.Fatalf that records the failure in -status-file first,
+// since os.Exit skips the deferred write in main.
+func fatalf(format string, v ...any) {
+	msg := fmt.Sprintf(format, v...)
+	writeStatusFile(errors.New(msg))
+	log.Fatal(msg)
+}
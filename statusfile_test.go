@@ -0,0 +1,36 @@
+package main
+
+import (
+	"encoding/json"
+	"errors"
+	"os"
+	"path/filepath"
+	"testing"
+)
+
+func TestWriteStatusFile(t *testing.T) {
+	oldPath, oldTotal := *statusFile, reportedTotal
+	t.Cleanup(func() { *statusFile, reportedTotal = oldPath, oldTotal })
+	*statusFile = filepath.Join(t.TempDir(), "status.json")
+	reportedTotal = 16
+
+	writeStatusFile(errors.New("timed out waiting for messages"))
+
+	b, err := os.ReadFile(*statusFile)
+	if err != nil {
+		t.Fatal(err)
+	}
+	var got runStatus
+	if err := json.Unmarshal(b, &got); err != nil {
+		t.Fatal(err)
+	}
+	if got.SchemaVersion != jsonSchemaVersion {
+		t.Errorf("schema_version = %d, want %d", got.SchemaVersion, jsonSchemaVersion)
+	}
+	if got.Success || got.Total != 16 || got.Error != "timed out waiting for messages" {
+		t.Errorf("status = %+v, want a failure with total 16 and the error", got)
+	}
+	if _, err := os.Stat(*statusFile + ".tmp"); !errors.Is(err, os.ErrNotExist) {
+		t.Errorf("temporary file left behind: %v", err)
+	}
+}
@@ -0,0 +1,86 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"strings"
+	"time"
+)
+
+var dateFormat = flag.String("date-format", "", "strftime-style format for displayed dates, e.g. %d/%m/%Y (default %Y-%m-%d); csv and json keep ISO dates")
+
+// strftimeLayouts maps the supported strftime directives to Go layouts.
+var strftimeLayouts = map[byte]string{
+	'Y': "2006",
+	'y': "06",
+	'm': "01",
+	'd': "02",
+	'e': "_2",
+	'j': "002",
+	'b': "Jan",
+	'B': "January",
+	'a': "Mon",
+	'A': "Monday",
+}
+
+// formatStrftime formats t according to a strftime pattern. Each directive is
+// formatted on its own, so literal text in the pattern is never mistaken for
+// part of a Go layout.
+func formatStrftime(t time.Time, pattern string) (string, error) {
+	var b strings.Builder
+	for i := 0; i < len(pattern); i++ {
+		if pattern[i] != '%' {
+			b.WriteByte(pattern[i])
+			continue
+		}
+
+		i++
+		if i == len(pattern) {
+			return "", fmt.Errorf("date format %q ends with a lone %%", pattern)
+		}
+		if pattern[i] == '%' {
+			b.WriteByte('%')
+			continue
+		}
+		layout, ok := strftimeLayouts[pattern[i]]
+		if !ok {
+			return "", fmt.Errorf("unsupported directive %%%c in date format %q", pattern[i], pattern)
+		}
+		b.WriteString(t.Format(layout))
+	}
+	return b.String(), nil
+}
+
+// validateDateFormat checks -date-format by formatting two known dates.
+func validateDateFormat() error {
+	if *dateFormat == "" {
+		return nil
+	}
+	first, err := formatStrftime(time.Date(2024, time.March, 14, 0, 0, 0, 0, time.UTC), *dateFormat)
+	if err != nil {
+		return err
+	}
+	// A format without day, month and year parts would print every row the
+	// same, which is only noticed after a full fetch
+	second, err := formatStrftime(time.Date(2025, time.November, 2, 0, 0, 0, 0, time.UTC), *dateFormat)
+	if err != nil {
+		return err
+	}
+	if first == second {
+		return fmt.Errorf("-date-format %q formats every date the same way (%q)", *dateFormat, first)
+	}
+	return nil
+}
+
+// displayDate formats a bucket date for text output using -date-format.
+// Bucket keys themselves stay ISO so they sort correctly.
+func displayDate(date time.Time) string {
+	if *dateFormat == "" {
+		return date.Format("2006-01-02")
+	}
+	formatted, err := formatStrftime(date, *dateFormat)
+	if err != nil {
+		return date.Format("2006-01-02")
+	}
+	return formatted
+}
@@ -0,0 +1,57 @@
+package main
+
+import (
+	"strings"
+	"testing"
+	"time"
+)
+
+func TestFormatStrftime(t *testing.T) {
+	date := time.Date(2024, time.March, 4, 0, 0, 0, 0, time.UTC)
+	tests := []struct {
+		pattern string
+		want    string
+	}{
+		{"%Y-%m-%d", "2024-03-04"},
+		{"%d/%m/%y", "04/03/24"},
+		{"%a %e %b", "Mon  4 Mar"},
+		{"%A, %B %d", "Monday, March 04"},
+		{"day %j", "day 064"},
+		{"100%% %Y", "100% 2024"},
+	}
+	for _, tt := range tests {
+		got, err := formatStrftime(date, tt.pattern)
+		if err != nil || got != tt.want {
+			t.Errorf("formatStrftime(%q) = %q, %v; want %q", tt.pattern, got, err, tt.want)
+		}
+	}
+}
+
+func TestValidateDateFormat(t *testing.T) {
+	oldFormat := *dateFormat
+	t.Cleanup(func() { *dateFormat = oldFormat })
+
+	tests := []struct {
+		format  string
+		wantErr string
+	}{
+		{"", ""},
+		{"%d/%m/%Y", ""},
+		{"%Y-%m-%d %H", "unsupported directive %H"},
+		{"%Y-%m-%", "ends with a lone %"},
+		{"spam", "formats every date the same way"},
+	}
+	for _, tt := range tests {
+		*dateFormat = tt.format
+		err := validateDateFormat()
+		if tt.wantErr == "" {
+			if err != nil {
+				t.Errorf("validateDateFormat with %q: %v", tt.format, err)
+			}
+			continue
+		}
+		if err == nil || !strings.Contains(err.Error(), tt.wantErr) {
+			t.Errorf("validateDateFormat with %q = %v, want an error containing %q", tt.format, err, tt.wantErr)
+		}
+	}
+}
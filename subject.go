@@ -0,0 +1,39 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"mime"
+	"regexp"
+
+	"google.golang.org/api/gmail/v1"
+)
+
+var subjectRegex = flag.String("subject-regex", "", "only count messages whose Subject matches this regular expression (RE2 syntax; prefix with (?i) to ignore case)")
+
+// subjectPattern is the compiled -subject-regex, or nil.
+var subjectPattern *regexp.Regexp
+
+func compileSubjectRegex() error {
+	if *subjectRegex == "" {
+		return nil
+	}
+	pattern, err := regexp.Compile(*subjectRegex)
+	if err != nil {
+		return fmt.Errorf("invalid -subject-regex: %v", err)
+	}
+	subjectPattern = pattern
+	return nil
+}
+
+// subjectMatches reports whether the message's Subject matches
+// -subject-regex. Encoded words (=?UTF-8?B?...?=) are decoded first, and a
+// subject that fails to decode is matched as sent.
+func subjectMatches(m *gmail.Message) bool {
+	subject := messageHeader(m, "Subject")
+	dec := new(mime.WordDecoder)
+	if decoded, err := dec.DecodeHeader(subject); err == nil {
+		subject = decoded
+	}
+	return subjectPattern.MatchString(subject)
+}
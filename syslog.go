@@ -0,0 +1,31 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"sort"
+)
+
+var useSyslog = flag.Bool("syslog", false, "also send the summary total to the system logger")
+var syslogDays = flag.Bool("syslog-days", false, "with -syslog, also send one line per day")
+var syslogTag = flag.String("syslog-tag", "gocheckspam", "tag for -syslog messages")
+var syslogFacility = flag.String("syslog-facility", "user", "facility for -syslog messages: user, daemon or local0-local7")
+
+// syslogLines formats the summary lines sent by -syslog.
+func syslogLines(spamCounts map[string]int) []string {
+	var dates []string
+	for date := range spamCounts {
+		dates = append(dates, date)
+	}
+	sort.Strings(dates)
+
+	var lines []string
+	total := 0
+	for _, date := range dates {
+		total += spamCounts[date]
+		if *syslogDays {
+			lines = append(lines, fmt.Sprintf("spam %s: %d", date, spamCounts[date]))
+		}
+	}
+	return append(lines, fmt.Sprintf("spam total for %s: %d", windowDescription, total))
+}
@@ -0,0 +1,11 @@
+//go:build windows || plan9
+
+package main
+
+import "fmt"
+
+// sendSyslogSummary reports that -syslog is unavailable; log/syslog is not
+// implemented on this platform.
+func sendSyslogSummary(spamCounts map[string]int) error {
+	return fmt.Errorf("syslog is not supported on this platform")
+}
@@ -0,0 +1,43 @@
+//go:build !windows && !plan9
+
+package main
+
+import (
+	"fmt"
+	"log/syslog"
+)
+
+var syslogFacilities = map[string]syslog.Priority{
+	"user":   syslog.LOG_USER,
+	"daemon": syslog.LOG_DAEMON,
+	"local0": syslog.LOG_LOCAL0,
+	"local1": syslog.LOG_LOCAL1,
+	"local2": syslog.LOG_LOCAL2,
+	"local3": syslog.LOG_LOCAL3,
+	"local4": syslog.LOG_LOCAL4,
+	"local5": syslog.LOG_LOCAL5,
+	"local6": syslog.LOG_LOCAL6,
+	"local7": syslog.LOG_LOCAL7,
+}
+
+// sendSyslogSummary writes the summary to the system logger. It is
+// independent of the log package output, which keeps going to stderr.
+func sendSyslogSummary(spamCounts map[string]int) error {
+	facility, ok := syslogFacilities[*syslogFacility]
+	if !ok {
+		return fmt.Errorf("unknown syslog facility %q", *syslogFacility)
+	}
+
+	w, err := syslog.New(facility|syslog.LOG_INFO, *syslogTag)
+	if err != nil {
+		return err
+	}
+	defer w.Close()
+
+	for _, line := range syslogLines(spamCounts) {
+		if err := w.Info(line); err != nil {
+			return err
+		}
+	}
+	return nil
+}
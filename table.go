@@ -0,0 +1,73 @@
+package main
+
+import (
+	"fmt"
+	"log"
+	"strconv"
+	"strings"
+	"time"
+)
+
+// printSpamTable prints the daily counts as an ASCII table with the count
+// column right-aligned to the width of the largest number.
+func printSpamTable(spamCounts map[string]int) {
+	dates := reportDates(spamCounts)
+
+	total := 0
+	for _, count := range spamCounts {
+		total += count
+	}
+	countWidth := max(len("Count"), len(strconv.Itoa(total)))
+
+	var rows []string
+	var dateValues []time.Time
+	dateWidth := len("Date")
+	for _, date := range dates {
+		dateValue, err := time.Parse("2006-01-02", date)
+		if err != nil {
+			log.Printf("Error parsing date: %v", err)
+			continue
+		}
+		rows = append(rows, date)
+		dateValues = append(dateValues, dateValue)
+		dateWidth = max(dateWidth, len(displayDate(dateValue)))
+	}
+
+	rule := fmt.Sprintf("%s  %s  %s", strings.Repeat("-", 3), strings.Repeat("-", dateWidth), strings.Repeat("-", countWidth))
+
+	fmt.Printf("%-3s  %-*s  %*s\n", "Day", dateWidth, "Date", countWidth, "Count")
+	fmt.Println(rule)
+	for i, date := range rows {
+		dateValue := dateValues[i]
+		fmt.Printf("%-3s  %-*s  %*d\n", dateValue.Format("Mon"), dateWidth, displayDate(dateValue), countWidth, spamCounts[date])
+	}
+	fmt.Println(rule)
+	fmt.Printf("%-*s  %*d\n", dateWidth+5, "Total", countWidth, total)
+	printUndatedNote()
+}
+
+// printSenderTable prints the -by-sender or -by-domain counts as an ASCII
+// table, like printSpamTable, with each row's percent of the total.
+func printSenderTable(senderCounts map[string]int) {
+	senders := sortedSenders(senderCounts)
+	total := countsTotal(senderCounts)
+
+	nameWidth := len(senderColumn())
+	for _, sender := range senders {
+		nameWidth = max(nameWidth, len(sender))
+	}
+	countWidth := max(len("Count"), len(strconv.Itoa(total)))
+	percentWidth := len("Percent")
+
+	rule := fmt.Sprintf("%s  %s  %s", strings.Repeat("-", nameWidth), strings.Repeat("-", countWidth), strings.Repeat("-", percentWidth))
+
+	title := strings.ToUpper(senderColumn()[:1]) + senderColumn()[1:]
+	fmt.Printf("%-*s  %*s  %*s\n", nameWidth, title, countWidth, "Count", percentWidth, "Percent")
+	fmt.Println(rule)
+	for _, sender := range senders {
+		count := senderCounts[sender]
+		fmt.Printf("%-*s  %*d  %*.1f%%\n", nameWidth, sender, countWidth, count, percentWidth-1, percentOf(count, total))
+	}
+	fmt.Println(rule)
+	fmt.Printf("%-*s  %*d\n", nameWidth, "Total", countWidth, total)
+}
@@ -0,0 +1,47 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"regexp"
+	"slices"
+	"strings"
+)
+
+var rowTemplate = flag.String("template", "{weekday} {date} {count}", "layout of each text output row; placeholders {date}, {weekday}, {count} and {total} (running total)")
+var footerTemplate = flag.String("footer-template", "Total: {total}", "layout of the text output total line; placeholder {total}")
+
+var rowPlaceholders = []string{"date", "weekday", "count", "total"}
+var footerPlaceholders = []string{"total"}
+
+// placeholderPattern matches a {name} placeholder in an output template.
+var placeholderPattern = regexp.MustCompile(`\{[^{}]*\}`)
+
+// validateTemplate rejects placeholders that are not in allowed, so a typo
+// fails up front instead of being printed literally.
+func validateTemplate(flagName, tmpl string, allowed []string) error {
+	for _, match := range placeholderPattern.FindAllString(tmpl, -1) {
+		if !slices.Contains(allowed, match[1:len(match)-1]) {
+			return fmt.Errorf("unknown placeholder %s in -%s (known: {%s})", match, flagName, strings.Join(allowed, "}, {"))
+		}
+	}
+	return nil
+}
+
+// validateTemplates checks -template and -footer-template.
+func validateTemplates() error {
+	if err := validateTemplate("template", *rowTemplate, rowPlaceholders); err != nil {
+		return err
+	}
+	return validateTemplate("footer-template", *footerTemplate, footerPlaceholders)
+}
+
+// expandTemplate substitutes values for the placeholders in tmpl.
+func expandTemplate(tmpl string, values map[string]string) string {
+	return placeholderPattern.ReplaceAllStringFunc(tmpl, func(match string) string {
+		if value, ok := values[match[1:len(match)-1]]; ok {
+			return value
+		}
+		return match
+	})
+}
@@ -0,0 +1,67 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"strconv"
+	"strings"
+)
+
+var domainTiers = flag.Bool("domain-tiers", false, "summarize sender domains into light, moderate and heavy volume tiers instead of listing each one")
+var tierThresholds = flag.String("tier-thresholds", "10,50", "with -domain-tiers, the \"low,high\" message counts separating light (< low), moderate (low to high) and heavy (> high) domains")
+
+// tierLow and tierHigh are the parsed -tier-thresholds.
+var tierLow, tierHigh int
+
+func parseTierThresholds() error {
+	low, high, ok := strings.Cut(*tierThresholds, ",")
+	if !ok {
+		return fmt.Errorf("invalid -tier-thresholds %q: want \"low,high\"", *tierThresholds)
+	}
+	var err error
+	if tierLow, err = strconv.Atoi(strings.TrimSpace(low)); err != nil {
+		return fmt.Errorf("invalid -tier-thresholds %q: %v", *tierThresholds, err)
+	}
+	if tierHigh, err = strconv.Atoi(strings.TrimSpace(high)); err != nil {
+		return fmt.Errorf("invalid -tier-thresholds %q: %v", *tierThresholds, err)
+	}
+	if tierLow < 1 || tierHigh < tierLow {
+		return fmt.Errorf("invalid -tier-thresholds %q: want 1 <= low <= high", *tierThresholds)
+	}
+	return nil
+}
+
+// domainTier is one row of the -domain-tiers summary.
+type domainTier struct {
+	name     string
+	bounds   string
+	domains  int
+	messages int
+}
+
+// tierDomains buckets the per-domain counts into volume tiers, heaviest first.
+func tierDomains(domainCounts map[string]int) []domainTier {
+	tiers := []domainTier{
+		{name: "heavy", bounds: fmt.Sprintf(">%d", tierHigh)},
+		{name: "moderate", bounds: fmt.Sprintf("%d-%d", tierLow, tierHigh)},
+		{name: "light", bounds: fmt.Sprintf("<%d", tierLow)},
+	}
+	for _, count := range domainCounts {
+		i := 1
+		if count > tierHigh {
+			i = 0
+		} else if count < tierLow {
+			i = 2
+		}
+		tiers[i].domains++
+		tiers[i].messages += count
+	}
+	return tiers
+}
+
+func printDomainTiers(domainCounts map[string]int) {
+	for _, tier := range tierDomains(domainCounts) {
+		fmt.Printf("%-8s %-9s %6d domains %8d messages\n", tier.name, "("+tier.bounds+")", tier.domains, tier.messages)
+	}
+	fmt.Printf("Total: %d domains\n", len(domainCounts))
+}
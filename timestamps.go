@@ -0,0 +1,42 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"sort"
+	"time"
+
+	"google.golang.org/api/gmail/v1"
+)
+
+// timestampLayout is ISO 8601 with millisecond precision and a zone offset.
+const timestampLayout = "2006-01-02T15:04:05.000Z07:00"
+
+// getSpamTimestamps returns the internalDate of every counted message in the
+// bucketing timezone, sorted chronologically.
+func getSpamTimestamps(ctx context.Context, srv *gmail.Service) ([]time.Time, error) {
+	messages, err := collectMessages(ctx, srv)
+	if err != nil {
+		return nil, err
+	}
+
+	var times []time.Time
+	for _, m := range messages {
+		// Reuse the date guards so invalid internalDates are skipped the same way
+		if _, ok := messageDate(m); !ok {
+			continue
+		}
+		times = append(times, time.UnixMilli(messageTimeMs(m)).In(location))
+	}
+	sort.Slice(times, func(i, j int) bool {
+		return times[i].Before(times[j])
+	})
+
+	return times, nil
+}
+
+func printTimestamps(times []time.Time) {
+	for _, t := range times {
+		fmt.Println(t.Format(timestampLayout))
+	}
+}
@@ -0,0 +1,63 @@
+package main
+
+import (
+	"context"
+	"sync"
+	"sync/atomic"
+
+	"google.golang.org/api/gmail/v1"
+)
+
+// exitWarnings is the exit status used by -strict when warnings were reported.
+const exitWarnings = 3
+
+// warnings counts conditions that may make the report incomplete or
+// inaccurate. Under -strict any of them makes the run exit with exitWarnings:
+//   - a message with a missing or invalid internalDate
+//   - a message fetch that failed after all retries
+//   - results truncated by -max-pages
+//   - a message that -delete could not move to Trash
+//   - a -webhook notification that failed or got a non-2xx response
+//   - a fetched message dropped because its listing was cancelled
+var warnings atomic.Int64
+
+// recordWarning notes that a warning condition occurred.
+func recordWarning() {
+	warnings.Add(1)
+}
+
+// warningCount returns the number of warnings reported so far.
+func warningCount() int64 {
+	return warnings.Load()
+}
+
+// undatedMessages holds the messages without a usable internalDate, so each
+// is counted once however many times it is inspected. They are keyed by the
+// fetched message rather than its ID, since under -accounts the same ID can
+// name different messages in different mailboxes.
+var undatedMessages sync.Map
+var undatedCount atomic.Int64
+
+// noteUndated records that a message was left out of the counts because it
+// has no usable internalDate.
+func noteUndated(m *gmail.Message) {
+	if _, loaded := undatedMessages.LoadOrStore(m, true); !loaded {
+		undatedCount.Add(1)
+		recordWarning()
+	}
+}
+
+// droppedMessages counts messages that were fetched but never handed to the
+// consumer because the listing was cancelled first (by -deadline, -timeout or
+// an error elsewhere), so the report may undercount.
+var droppedMessages atomic.Int64
+
+// noteDropped records a fetched message that could not be delivered, for the
+// run and for the account ctx is fetching.
+func noteDropped(ctx context.Context) {
+	droppedMessages.Add(1)
+	if run := accountFrom(ctx); run != nil {
+		run.dropped.Add(1)
+	}
+	recordWarning()
+}
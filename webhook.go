@@ -0,0 +1,76 @@
+package main
+
+import (
+	"bytes"
+	"context"
+	"flag"
+	"fmt"
+	"io"
+	"log"
+	"net/http"
+	"strings"
+
+	"golang.org/x/oauth2"
+)
+
+var webhookURL = flag.String("webhook", "", "POST the JSON report (as printed by -format json) to this URL after counting")
+
+// webhookHeaders holds the -webhook-header values, each "Name: value".
+var webhookHeaders stringList
+
+func init() {
+	flag.Var(&webhookHeaders, "webhook-header", "extra \"Name: value\" header for -webhook requests, e.g. an Authorization token; repeatable")
+}
+
+// validateWebhook checks the -webhook-header values up front so a typo
+// doesn't surface only after the whole count has run.
+func validateWebhook() error {
+	if len(webhookHeaders) > 0 && *webhookURL == "" {
+		return fmt.Errorf("-webhook-header requires -webhook")
+	}
+	for _, header := range webhookHeaders {
+		if name, _, ok := strings.Cut(header, ":"); !ok || strings.TrimSpace(name) == "" {
+			return fmt.Errorf("invalid -webhook-header %q: want \"Name: value\"", header)
+		}
+	}
+	return nil
+}
+
+// postWebhook sends the JSON report to -webhook through the same HTTP client
+// (and proxy) used for Google requests. Callers treat a failure, including a
+// non-2xx response, as a warning so it only fails the run under -strict.
+func postWebhook(ctx context.Context, spamCounts map[string]int) error {
+	var body bytes.Buffer
+	if err := writeJSONSummary(&body, spamCounts); err != nil {
+		return err
+	}
+
+	req, err := http.NewRequestWithContext(ctx, http.MethodPost, *webhookURL, &body)
+	if err != nil {
+		return fmt.Errorf("invalid -webhook URL: %v", err)
+	}
+	req.Header.Set("Content-Type", "application/json")
+	for _, header := range webhookHeaders {
+		name, value, _ := strings.Cut(header, ":")
+		req.Header.Set(strings.TrimSpace(name), strings.TrimSpace(value))
+	}
+
+	client := http.DefaultClient
+	if c, ok := ctx.Value(oauth2.HTTPClient).(*http.Client); ok {
+		client = c
+	}
+	resp, err := client.Do(req)
+	if err != nil {
+		return fmt.Errorf("webhook request failed: %v", err)
+	}
+	defer resp.Body.Close()
+	io.Copy(io.Discard, resp.Body)
+
+	if resp.StatusCode < 200 || resp.StatusCode > 299 {
+		return fmt.Errorf("webhook returned %s", resp.Status)
+	}
+	if *debug {
+		log.Printf("Webhook returned %s", resp.Status)
+	}
+	return nil
+}
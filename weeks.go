@@ -0,0 +1,66 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"log"
+	"sort"
+	"time"
+)
+
+var granularity = flag.String("granularity", "day", "bucket size for JSON output: day, or week to add ISO week entries (2024-W11) alongside the daily counts")
+
+func validateGranularity() error {
+	switch *granularity {
+	case "day":
+		return nil
+	case "week":
+		if *format != "json" && *format != "jsonl" && *jsonOut == "" {
+			return fmt.Errorf("-granularity week only applies to -format json or jsonl and -json-out")
+		}
+		return nil
+	}
+	return fmt.Errorf("unknown -granularity %q: want day or week", *granularity)
+}
+
+// weekEntry is one ISO week in -granularity week JSON output. Start and End
+// are the week's Monday and Sunday, so consumers never have to work out
+// which dates an ISO week spans around the new year.
+type weekEntry struct {
+	Week  string `json:"week"`
+	Start string `json:"start"`
+	End   string `json:"end"`
+	Count int    `json:"count"`
+}
+
+// weeklyCounts groups the daily counts by ISO week, oldest first. Days late
+// in December can belong to week 1 of the next year, and early January days
+// to the last week of the previous one; ISOWeek handles both.
+func weeklyCounts(spamCounts map[string]int) []weekEntry {
+	byWeek := make(map[string]*weekEntry)
+	for date, count := range spamCounts {
+		dateValue, err := time.Parse("2006-01-02", date)
+		if err != nil {
+			log.Printf("Error parsing date: %v", err)
+			continue
+		}
+		year, week := dateValue.ISOWeek()
+		key := fmt.Sprintf("%04d-W%02d", year, week)
+		entry := byWeek[key]
+		if entry == nil {
+			start := weekStart(dateValue)
+			monday, _ := time.Parse("2006-01-02", start)
+			entry = &weekEntry{Week: key, Start: start, End: monday.AddDate(0, 0, 6).Format("2006-01-02")}
+			byWeek[key] = entry
+		}
+		entry.Count += count
+	}
+
+	var weeks []weekEntry
+	for _, entry := range byWeek {
+		weeks = append(weeks, *entry)
+	}
+	// Start dates sort chronologically, unlike week keys across years
+	sort.Slice(weeks, func(i, j int) bool { return weeks[i].Start < weeks[j].Start })
+	return weeks
+}
@@ -0,0 +1,242 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"log"
+	"math"
+	"strconv"
+	"strings"
+	"time"
+)
+
+var lastWeek = flag.Bool("last-week", false, "count spam from the previous ISO week (Monday to Sunday)")
+var lastMonth = flag.Bool("last-month", false, "count spam from the previous calendar month")
+var thisMonth = flag.Bool("this-month", false, "count spam since the start of the current month")
+var businessDays = flag.Int("business-days", 0, "count spam since N weekdays ago, skipping Saturdays and Sundays (public holidays are not skipped)")
+var todayOnly = flag.Bool("today", false, "count spam received today")
+var within = flag.String("within", "", "count spam from this long ago until now, e.g. 36h, 3d or 1w (sub-day windows are honored exactly by internalDate)")
+var asOf = flag.String("as-of", "", "end the -days window on this YYYY-MM-DD date (inclusive) instead of today, to regenerate a past report")
+var allMessages = flag.Bool("all", false, "count every message in the label regardless of date (may be very slow for large mailboxes)")
+
+// endDate is the exclusive end of the query window in YYYY-MM-DD format, or
+// "" when the window runs up to now.
+var endDate string
+
+// windowDescription names the query window in report headers.
+var windowDescription string
+
+// windowLabel is a short name for the query window, e.g. "30d".
+var windowLabel string
+
+// cutoffTime is the exact start of a -within window, or zero when the window
+// starts at midnight of cutoffDate.
+var cutoffTime time.Time
+
+// windowDays is the number of days in the query window, used for per-day
+// averages so that days without spam still count.
+var windowDays int
+
+// flagWasSet reports whether the named flag was given on the command line.
+// Flags set from the environment don't count, so they never conflict with
+// what was typed.
+func flagWasSet(name string) bool {
+	return commandLineFlags[name]
+}
+
+// maxDays bounds -days and -business-days far beyond the age of any mailbox,
+// so a fat-fingered value fails clearly instead of producing a cutoff date
+// thousands of years away.
+const maxDays = 100 * 366
+
+// resolveWindow sets cutoffDate, endDate and windowDescription from -days or
+// one of the calendar window flags, computing day boundaries in the
+// bucketing timezone.
+func resolveWindow(now time.Time) error {
+	windows := []struct {
+		name string
+		on   bool
+	}{
+		{"-last-week", *lastWeek},
+		{"-last-month", *lastMonth},
+		{"-this-month", *thisMonth},
+		{"-today", *todayOnly},
+		{"-business-days", *businessDays > 0},
+		{"-within", *within != ""},
+		{"-all", *allMessages},
+	}
+
+	if *days < 0 || *days > maxDays {
+		return fmt.Errorf("-days must be between 0 and %d", maxDays)
+	}
+	if *businessDays < 0 || *businessDays > maxDays {
+		return fmt.Errorf("-business-days must be between 0 and %d", maxDays)
+	}
+
+	var active []string
+	for _, window := range windows {
+		if window.on {
+			active = append(active, window.name)
+		}
+	}
+	if len(active) > 1 {
+		return fmt.Errorf("%s are mutually exclusive", strings.Join(active, " and "))
+	}
+	if len(active) == 1 && flagWasSet("days") {
+		return fmt.Errorf("%s cannot be combined with -days", active[0])
+	}
+	if len(active) == 1 && *asOf != "" {
+		return fmt.Errorf("%s cannot be combined with -as-of", active[0])
+	}
+	if *allMessages {
+		// windowDays is set from the data once it has been counted
+		cutoffDate, endDate = "", ""
+		windowDescription = "all time (date filtering disabled)"
+		windowLabel = "all"
+		windowDays = 0
+		return nil
+	}
+
+	now = now.In(location)
+	if *within != "" {
+		d, err := parseWithin(*within)
+		if err != nil {
+			return err
+		}
+		// Gmail's after: is date-granular, so the query starts on the
+		// cutoff's day and keepMessage trims to the exact time
+		cutoffTime = now.Add(-d)
+		cutoffDate = cutoffTime.Format("2006-01-02")
+		endDate = ""
+		windowDescription = "the past " + *within
+		windowLabel = *within
+		windowDays = max(1, int(math.Ceil(d.Hours()/24)))
+		return nil
+	}
+
+	midnight := time.Date(now.Year(), now.Month(), now.Day(), 0, 0, 0, 0, location)
+	monthStart := time.Date(now.Year(), now.Month(), 1, 0, 0, 0, 0, location)
+
+	var start, end time.Time
+	switch {
+	case *lastWeek:
+		// ISO weeks start on Monday
+		monday := midnight.AddDate(0, 0, -((int(midnight.Weekday()) + 6) % 7))
+		start, end = monday.AddDate(0, 0, -7), monday
+	case *lastMonth:
+		start, end = monthStart.AddDate(0, -1, 0), monthStart
+	case *thisMonth:
+		start = monthStart
+	case *todayOnly:
+		start = midnight
+	case *businessDays > 0:
+		start = businessDaysBack(midnight, *businessDays)
+	case *asOf != "":
+		// As if run on -as-of: the same -days lookback, through that day
+		day, err := time.ParseInLocation("2006-01-02", *asOf, location)
+		if err != nil {
+			return fmt.Errorf("invalid -as-of %q: want YYYY-MM-DD", *asOf)
+		}
+		if day.After(midnight) {
+			return fmt.Errorf("-as-of %s is in the future", *asOf)
+		}
+		start, end = day.AddDate(0, 0, -*days), day.AddDate(0, 0, 1)
+	default:
+		cutoffDate = now.AddDate(0, 0, -*days).Format("2006-01-02")
+		endDate = ""
+		windowDescription = fmt.Sprintf("the past %v days", *days)
+		windowLabel = fmt.Sprintf("%dd", *days)
+		// after: includes the cutoff's day and the window runs through
+		// today, so it spans days+1 dates, as -as-of does
+		windowDays = daysBetween(midnight.AddDate(0, 0, -*days), midnight.AddDate(0, 0, 1))
+		return nil
+	}
+
+	cutoffDate = start.Format("2006-01-02")
+	if end.IsZero() {
+		// The window runs through the end of today
+		windowDays = daysBetween(start, midnight.AddDate(0, 0, 1))
+		endDate = ""
+		windowDescription = fmt.Sprintf("%s through today", cutoffDate)
+	} else {
+		endDate = end.Format("2006-01-02")
+		windowDays = daysBetween(start, end)
+		windowDescription = fmt.Sprintf("%s through %s", cutoffDate, end.AddDate(0, 0, -1).Format("2006-01-02"))
+	}
+	windowLabel = windowDescription
+	log.Printf("Counting spam from %s (%s)", windowDescription, location)
+
+	return nil
+}
+
+// businessDaysBack walks back n weekdays from day, skipping Saturdays and
+// Sundays. Public holidays are not known and count as business days.
+func businessDaysBack(day time.Time, n int) time.Time {
+	for n > 0 {
+		day = day.AddDate(0, 0, -1)
+		if weekday := day.Weekday(); weekday != time.Saturday && weekday != time.Sunday {
+			n--
+		}
+	}
+	return day
+}
+
+// daysBetween counts the calendar days from start up to (not including) end,
+// both of which are midnights in the same location.
+func daysBetween(start, end time.Time) int {
+	// Round to absorb daylight saving transitions
+	return int(math.Round(end.Sub(start).Hours() / 24))
+}
+
+// settleAllTimeWindow starts an -all window at the oldest counted day, so
+// per-day averages and the zero-filled series cover the days spent
+// collecting spam rather than an unbounded span.
+func settleAllTimeWindow(spamCounts map[string]int, now time.Time) {
+	oldest := ""
+	for date := range spamCounts {
+		if oldest == "" || date < oldest {
+			oldest = date
+		}
+	}
+	if oldest == "" {
+		return
+	}
+	start, err := time.ParseInLocation("2006-01-02", oldest, location)
+	if err != nil {
+		return
+	}
+	now = now.In(location)
+	tomorrow := time.Date(now.Year(), now.Month(), now.Day(), 0, 0, 0, 0, location).AddDate(0, 0, 1)
+	cutoffDate = oldest
+	windowDays = daysBetween(start, tomorrow)
+}
+
+// parseWithin parses a -within duration. On top of time.ParseDuration units
+// it accepts whole days ("3d") and weeks ("1w").
+func parseWithin(value string) (time.Duration, error) {
+	units := []struct {
+		suffix string
+		name   string
+		length time.Duration
+	}{
+		{"d", "days", 24 * time.Hour},
+		{"w", "weeks", 7 * 24 * time.Hour},
+	}
+	for _, unit := range units {
+		if number, ok := strings.CutSuffix(value, unit.suffix); ok {
+			n, err := strconv.Atoi(number)
+			if err != nil || n <= 0 {
+				return 0, fmt.Errorf("invalid -within %q: want a positive whole number of %s", value, unit.name)
+			}
+			return time.Duration(n) * unit.length, nil
+		}
+	}
+	d, err := time.ParseDuration(value)
+	if err != nil {
+		return 0, fmt.Errorf("invalid -within %q: %v", value, err)
+	}
+	if d <= 0 {
+		return 0, fmt.Errorf("invalid -within %q: must be positive", value)
+	}
+	return d, nil
+}
@@ -0,0 +1,91 @@
+package main
+
+import (
+	"testing"
+	"time"
+)
+
+func TestBusinessDaysBack(t *testing.T) {
+	tests := []struct {
+		name  string
+		start string
+		n     int
+		want  string
+	}{
+		{"saturday, one day", "2024-03-02", 1, "2024-03-01"},
+		{"saturday, a week", "2024-03-02", 5, "2024-02-26"},
+		{"sunday, one day", "2024-03-03", 1, "2024-03-01"},
+		{"sunday, two days", "2024-03-03", 2, "2024-02-29"},
+		{"monday, one day skips the weekend", "2024-03-04", 1, "2024-03-01"},
+		{"monday, a week", "2024-03-04", 5, "2024-02-26"},
+		{"monday, zero days", "2024-03-04", 0, "2024-03-04"},
+		{"tuesday, one day", "2024-03-05", 1, "2024-03-04"},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			start, err := time.Parse("2006-01-02", tt.start)
+			if err != nil {
+				t.Fatal(err)
+			}
+			got := businessDaysBack(start, tt.n).Format("2006-01-02")
+			if got != tt.want {
+				t.Errorf("businessDaysBack(%s, %d) = %s, want %s", tt.start, tt.n, got, tt.want)
+			}
+		})
+	}
+}
+
+func TestResolveWindowDaysRange(t *testing.T) {
+	setTestWindow(t)
+	oldDays, oldBusinessDays, oldLabel := *days, *businessDays, windowLabel
+	t.Cleanup(func() { *days, *businessDays, windowLabel = oldDays, oldBusinessDays, oldLabel })
+	now := time.Date(2024, 3, 8, 12, 0, 0, 0, time.UTC)
+
+	*days = maxDays
+	if err := resolveWindow(now); err != nil {
+		t.Errorf("resolveWindow with -days %d: %v", maxDays, err)
+	}
+	if windowDays != maxDays+1 {
+		t.Errorf("windowDays = %d, want %d", windowDays, maxDays+1)
+	}
+	*days = maxDays + 1
+	if err := resolveWindow(now); err == nil {
+		t.Errorf("resolveWindow accepted -days %d", maxDays+1)
+	}
+	*days = 7
+
+	*businessDays = maxDays
+	if err := resolveWindow(now); err != nil {
+		t.Errorf("resolveWindow with -business-days %d: %v", maxDays, err)
+	}
+	*businessDays = maxDays + 1
+	if err := resolveWindow(now); err == nil {
+		t.Errorf("resolveWindow accepted -business-days %d", maxDays+1)
+	}
+}
+
+func TestResolveWindowDaysMatchesAsOf(t *testing.T) {
+	setTestWindow(t)
+	oldDays, oldAsOf, oldLabel := *days, *asOf, windowLabel
+	t.Cleanup(func() { *days, *asOf, windowLabel = oldDays, oldAsOf, oldLabel })
+	now := time.Date(2024, 3, 8, 12, 0, 0, 0, time.UTC)
+	*days = 7
+
+	*asOf = ""
+	if err := resolveWindow(now); err != nil {
+		t.Fatal(err)
+	}
+	defaultDays, defaultCutoff := windowDays, cutoffDate
+
+	// -as-of today is the same window
+	*asOf = "2024-03-08"
+	if err := resolveWindow(now); err != nil {
+		t.Fatal(err)
+	}
+	if windowDays != defaultDays || cutoffDate != defaultCutoff {
+		t.Errorf("-as-of today: windowDays %d from %s, default -days: windowDays %d from %s", windowDays, cutoffDate, defaultDays, defaultCutoff)
+	}
+	if defaultDays != 8 {
+		t.Errorf("-days 7 windowDays = %d, want 8 (2024-03-01 through 2024-03-08)", defaultDays)
+	}
+}